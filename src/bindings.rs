@@ -0,0 +1,376 @@
+/*!
+Key bindings.
+
+Every key press is looked up in [`Bindings`] to find the [`Command`] to run. The defaults can
+be overridden by a TOML file that maps commands to lists of keys:
+
+```toml
+pan-up = ["Up", "I"]
+screenshot = ["F12", "Snapshot"]
+```
+
+Keys are named after winit's [`VirtualKeyCode`]. A command that's in the file loses its default
+keys. Each key can only run one command, so a key in the file can't be bound to another command
+in the file, or by default to a command that isn't in the file.
+*/
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use fnv::FnvHashMap;
+use winit::event::VirtualKeyCode;
+
+/// Something that a key can do.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Command {
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    /// Go back to the default view.
+    Reset,
+    /// Go back to the previous view in the history.
+    Undo,
+    /// Go forward to the next view in the history.
+    Redo,
+    /// Go back to the view that the history started at.
+    Home,
+    /// Save the view to the bookmarks file, after typing in a name.
+    SaveBookmark,
+    /// Print the bookmarks.
+    ListBookmarks,
+    NextBookmark,
+    PreviousBookmark,
+    IncreaseIterationLimit,
+    DecreaseIterationLimit,
+    IncreaseExponent,
+    DecreaseExponent,
+    /// Increase the exponent by `0.1`.
+    IncreaseExponentFine,
+    /// Decrease the exponent by `0.1`.
+    DecreaseExponentFine,
+    NextFormula,
+    ReloadFormula,
+    NextEngine,
+    NextPalette,
+    PrintView,
+    Screenshot,
+    /// Write the view to Kalles Fraktaler and Fractint files.
+    ExportLocation,
+    /// Write every setting to a scene file.
+    SaveScene,
+    /// Show or hide the heads-up display.
+    ToggleHud,
+}
+
+impl Command {
+    pub const ALL: [Command; 29] = [
+        Command::PanLeft,
+        Command::PanRight,
+        Command::PanUp,
+        Command::PanDown,
+        Command::ZoomIn,
+        Command::ZoomOut,
+        Command::Reset,
+        Command::Undo,
+        Command::Redo,
+        Command::Home,
+        Command::SaveBookmark,
+        Command::ListBookmarks,
+        Command::NextBookmark,
+        Command::PreviousBookmark,
+        Command::IncreaseIterationLimit,
+        Command::DecreaseIterationLimit,
+        Command::IncreaseExponent,
+        Command::DecreaseExponent,
+        Command::IncreaseExponentFine,
+        Command::DecreaseExponentFine,
+        Command::NextFormula,
+        Command::ReloadFormula,
+        Command::NextEngine,
+        Command::NextPalette,
+        Command::PrintView,
+        Command::Screenshot,
+        Command::ExportLocation,
+        Command::SaveScene,
+        Command::ToggleHud,
+    ];
+
+    /// The name of the command in a bindings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Command::PanLeft => "pan-left",
+            Command::PanRight => "pan-right",
+            Command::PanUp => "pan-up",
+            Command::PanDown => "pan-down",
+            Command::ZoomIn => "zoom-in",
+            Command::ZoomOut => "zoom-out",
+            Command::Reset => "reset",
+            Command::Undo => "undo",
+            Command::Redo => "redo",
+            Command::Home => "home",
+            Command::SaveBookmark => "save-bookmark",
+            Command::ListBookmarks => "list-bookmarks",
+            Command::NextBookmark => "next-bookmark",
+            Command::PreviousBookmark => "previous-bookmark",
+            Command::IncreaseIterationLimit => "increase-iteration-limit",
+            Command::DecreaseIterationLimit => "decrease-iteration-limit",
+            Command::IncreaseExponent => "increase-exponent",
+            Command::DecreaseExponent => "decrease-exponent",
+            Command::IncreaseExponentFine => "increase-exponent-fine",
+            Command::DecreaseExponentFine => "decrease-exponent-fine",
+            Command::NextFormula => "next-formula",
+            Command::ReloadFormula => "reload-formula",
+            Command::NextEngine => "next-engine",
+            Command::NextPalette => "next-palette",
+            Command::PrintView => "print-view",
+            Command::Screenshot => "screenshot",
+            Command::ExportLocation => "export-location",
+            Command::SaveScene => "save-scene",
+            Command::ToggleHud => "toggle-hud",
+        }
+    }
+
+    /// The keys that run the command, unless it's rebound.
+    fn default_keys(self) -> &'static [VirtualKeyCode] {
+        use VirtualKeyCode::*;
+
+        match self {
+            Command::PanLeft => &[Left, A],
+            Command::PanRight => &[Right, D],
+            Command::PanUp => &[Up, W],
+            Command::PanDown => &[Down, S],
+            Command::ZoomIn => &[Equals, Plus, NumpadAdd],
+            Command::ZoomOut => &[Minus, NumpadSubtract],
+            Command::Reset => &[R],
+            Command::Undo => &[Z, Back],
+            Command::Redo => &[Y],
+            Command::Home => &[Home],
+            Command::SaveBookmark => &[B],
+            Command::ListBookmarks => &[K],
+            Command::NextBookmark => &[N],
+            Command::PreviousBookmark => &[M],
+            Command::IncreaseIterationLimit => &[PageUp],
+            Command::DecreaseIterationLimit => &[PageDown],
+            Command::IncreaseExponent => &[RBracket],
+            Command::DecreaseExponent => &[LBracket],
+            Command::IncreaseExponentFine => &[Period],
+            Command::DecreaseExponentFine => &[Comma],
+            Command::NextFormula => &[F],
+            Command::ReloadFormula => &[L],
+            Command::NextEngine => &[P],
+            Command::NextPalette => &[C],
+            Command::PrintView => &[V],
+            Command::Screenshot => &[F12],
+            Command::ExportLocation => &[E],
+            Command::SaveScene => &[O],
+            Command::ToggleHud => &[H],
+        }
+    }
+}
+
+/// The command bound to each key.
+pub struct Bindings {
+    commands: FnvHashMap<VirtualKeyCode, Command>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Bindings {
+            commands: Command::ALL
+                .into_iter()
+                .flat_map(|command| {
+                    command
+                        .default_keys()
+                        .iter()
+                        .map(move |&keycode| (keycode, command))
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Bindings {
+    /// Load bindings from the TOML file at `path`, on top of the defaults.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+
+        let source = std::fs::read_to_string(&path).map_err(|err| Error::Io {
+            path: path.clone(),
+            err,
+        })?;
+
+        let overrides: BTreeMap<String, Vec<VirtualKeyCode>> =
+            toml::from_str(&source).map_err(|err| Error::Toml {
+                path: path.clone(),
+                err,
+            })?;
+        let overrides = overrides
+            .into_iter()
+            .map(|(name, keycodes)| {
+                match Command::ALL
+                    .into_iter()
+                    .find(|command| command.name() == name)
+                {
+                    Some(command) => Ok((command, keycodes)),
+                    None => Err(Error::UnknownCommand {
+                        path: path.clone(),
+                        name,
+                    }),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut bindings = Bindings::default();
+        bindings.commands.retain(|_, command| {
+            !overrides
+                .iter()
+                .any(|(overridden, _)| overridden == command)
+        });
+        for (command, keycodes) in overrides {
+            for keycode in keycodes {
+                if let Some(other) = bindings
+                    .commands
+                    .insert(keycode, command)
+                    .filter(|other| *other != command)
+                {
+                    return Err(Error::DuplicateKey {
+                        path,
+                        keycode,
+                        commands: [other, command],
+                    });
+                }
+            }
+        }
+        Ok(bindings)
+    }
+
+    pub fn command(&self, keycode: VirtualKeyCode) -> Option<Command> {
+        self.commands.get(&keycode).copied()
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        err: std::io::Error,
+    },
+    Toml {
+        path: PathBuf,
+        err: toml::de::Error,
+    },
+    UnknownCommand {
+        path: PathBuf,
+        name: String,
+    },
+    /// `keycode` is bound to both `commands`.
+    DuplicateKey {
+        path: PathBuf,
+        keycode: VirtualKeyCode,
+        commands: [Command; 2],
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io { path, err } => write!(f, "failed to read {}: {}", path.display(), err),
+            Error::Toml { path, err } => {
+                write!(f, "invalid key bindings in {}: {}", path.display(), err)
+            }
+            Error::UnknownCommand { path, name } => {
+                write!(f, "unknown command in {}: {:?}", path.display(), name)
+            }
+            Error::DuplicateKey {
+                path,
+                keycode,
+                commands: [first, second],
+            } => write!(
+                f,
+                "duplicate key in {}: {:?} is bound to both {} and {}",
+                path.display(),
+                keycode,
+                first.name(),
+                second.name()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, TemporaryFile};
+
+    #[test]
+    fn keys_in_the_file_replace_the_defaults() {
+        let file = TemporaryFile::new("bindings", "valid.toml");
+        // `pan-down` takes `Up` from `pan-up`, which is rebound too.
+        std::fs::write(
+            file.path(),
+            "pan-up = [\"I\"]\npan-down = [\"Up\", \"Down\"]\nzoom-in = []\n",
+        )
+        .unwrap();
+        let bindings = Bindings::load(file.path()).unwrap();
+
+        for (keycode, command) in [
+            (VirtualKeyCode::I, Some(Command::PanUp)),
+            (VirtualKeyCode::W, None),
+            (VirtualKeyCode::Up, Some(Command::PanDown)),
+            (VirtualKeyCode::Down, Some(Command::PanDown)),
+            (VirtualKeyCode::S, None),
+            (VirtualKeyCode::Equals, None),
+            (VirtualKeyCode::Left, Some(Command::PanLeft)),
+            (VirtualKeyCode::R, Some(Command::Reset)),
+        ] {
+            assert_eq!(bindings.command(keycode), command, "{:?}", keycode);
+        }
+    }
+
+    #[test]
+    fn keys_bound_to_two_commands_are_rejected() {
+        let file = TemporaryFile::new("bindings", "duplicate.toml");
+        for (source, expected) in [
+            (
+                "pan-down = [\"I\"]\npan-up = [\"I\"]",
+                [Command::PanDown, Command::PanUp],
+            ),
+            // `R` is still bound to `reset` by default.
+            ("pan-up = [\"R\"]", [Command::Reset, Command::PanUp]),
+        ] {
+            std::fs::write(file.path(), source).unwrap();
+            match Bindings::load(file.path()) {
+                Err(err @ Error::DuplicateKey { commands, .. }) => {
+                    assert_eq!(commands, expected, "{:?}", source);
+                    let message = err.to_string();
+                    for command in expected {
+                        assert!(message.contains(command.name()), "{}", message);
+                    }
+                }
+                result => panic!("{:?} from {:?}", result.err(), source),
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_bindings_are_rejected() {
+        test_support::assert_rejected(
+            &TemporaryFile::new("bindings", "invalid.toml"),
+            [
+                "fly-away = [\"Q\"]",
+                "pan-up = [\"NotAKey\"]",
+                "pan-up = \"W\"",
+            ],
+            |path| Bindings::load(path),
+            |err| matches!(err, Error::UnknownCommand { .. } | Error::Toml { .. }),
+        );
+    }
+}
@@ -0,0 +1,370 @@
+/*!
+Bookmarked views, in TOML files that can be edited by hand and shared:
+
+```toml
+[[bookmark]]
+name = "Seahorse valley"
+real = "-0.743643887037"
+imaginary = "0.131825904205"
+zoom = 2e9
+formula = "multibrot"
+exponent = 2.0
+iteration-limit = 65536
+palette = "fire"
+```
+
+The center is written as strings, so that it isn't rounded to `f64`. Only `name`, `real`,
+`imaginary` and `zoom` are required; a bookmark without the other settings leaves them as they
+are when it's jumped to.
+
+Locations from other programs can be read as bookmarks too, from Kalles Fraktaler `.kfr` files
+(see [`kfr`]) and Fractint `.par` files (see [`par`]), as can images saved by this program (see
+[`scene`]).
+*/
+
+use std::{
+    fmt::Display,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use dashu_float::DBig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    colour::Palette,
+    formula::Formula,
+    kfr::{self, ParseKfrError},
+    par::{self, ParseParError},
+    scene,
+    view::View,
+};
+
+/// A named view, and optionally the settings that it was saved with.
+#[derive(Clone, Debug)]
+pub struct Bookmark {
+    pub name: String,
+    pub view: View,
+    pub formula: Option<Formula>,
+    pub exponent: Option<f32>,
+    pub iteration_limit: Option<u32>,
+    pub palette: Option<Palette>,
+}
+
+/// A [`Bookmark`], as it's written in a file.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct BookmarkEntry {
+    name: String,
+    real: String,
+    imaginary: String,
+    zoom: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    formula: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exponent: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iteration_limit: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    palette: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BookmarksFile {
+    #[serde(default, rename = "bookmark")]
+    bookmarks: Vec<BookmarkEntry>,
+}
+
+impl Bookmark {
+    fn from_entry(entry: BookmarkEntry) -> Result<Self, String> {
+        let real = DBig::from_str(&entry.real)
+            .map_err(|_| format!("invalid real part: {:?}", entry.real))?;
+        let imaginary = DBig::from_str(&entry.imaginary)
+            .map_err(|_| format!("invalid imaginary part: {:?}", entry.imaginary))?;
+        if !(entry.zoom.is_finite() && entry.zoom > 0.0) {
+            return Err(format!("invalid zoom: {} (must be positive)", entry.zoom));
+        }
+        if entry.iteration_limit == Some(0) {
+            return Err("invalid iteration limit: 0 (must be positive)".to_string());
+        }
+        if let Some(exponent) = entry
+            .exponent
+            .filter(|exponent| !(exponent.is_finite() && *exponent >= 1.0))
+        {
+            return Err(format!(
+                "invalid exponent: {} (must be at least 1)",
+                exponent
+            ));
+        }
+
+        Ok(Bookmark {
+            view: View::new(real, imaginary, entry.zoom),
+            formula: entry
+                .formula
+                .map(|formula| formula.parse())
+                .transpose()
+                .map_err(|err| format!("{}", err))?,
+            exponent: entry.exponent,
+            iteration_limit: entry.iteration_limit,
+            palette: entry
+                .palette
+                .map(|palette| palette.parse())
+                .transpose()
+                .map_err(|err| format!("{}", err))?,
+            name: entry.name,
+        })
+    }
+
+    fn to_entry(&self) -> BookmarkEntry {
+        BookmarkEntry {
+            name: self.name.clone(),
+            real: self.view.real().to_string(),
+            imaginary: self.view.imaginary().to_string(),
+            zoom: self.view.zoom(),
+            formula: self.formula.map(|formula| formula.name().to_string()),
+            exponent: self.exponent,
+            iteration_limit: self.iteration_limit,
+            palette: self.palette.map(|palette| palette.name().to_string()),
+        }
+    }
+}
+
+/**
+Read the bookmarks in the file at `path`, in the order they're written.
+
+Files ending in `.kfr` and `.par` are read as Kalles Fraktaler and Fractint files. A `.kfr` file
+is a single bookmark named after the file, and so is a `.png` image, from the scene in it.
+*/
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Bookmark>, Error> {
+    let path = path.as_ref().to_path_buf();
+    let file_name = || {
+        path.file_stem()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+    };
+
+    if extension(&path).as_deref() == Some("png") {
+        let scene = scene::load(&path).map_err(Error::Scene)?;
+        let view = scene.view.ok_or_else(|| Error::Invalid {
+            path: path.clone(),
+            name: file_name(),
+            reason: "the image's scene has no view".to_string(),
+        })?;
+        return Ok(vec![Bookmark {
+            name: file_name(),
+            view,
+            formula: scene.formula,
+            exponent: scene.exponent,
+            iteration_limit: scene.iteration_limit,
+            palette: scene.palette,
+        }]);
+    }
+
+    let source = std::fs::read_to_string(&path).map_err(|err| Error::Io {
+        path: path.clone(),
+        err,
+    })?;
+
+    match extension(&path).as_deref() {
+        Some("kfr") => {
+            return kfr::parse(&file_name(), &source)
+                .map(|bookmark| vec![bookmark])
+                .map_err(|err| Error::Kfr {
+                    path: path.clone(),
+                    err,
+                });
+        }
+        Some("par") => {
+            return par::parse(&source).map_err(|err| Error::Par {
+                path: path.clone(),
+                err,
+            })
+        }
+        _ => {}
+    }
+
+    let file: BookmarksFile = toml::from_str(&source).map_err(|err| Error::Toml {
+        path: path.clone(),
+        err,
+    })?;
+
+    file.bookmarks
+        .into_iter()
+        .map(|entry| {
+            let name = entry.name.clone();
+            Bookmark::from_entry(entry).map_err(|reason| Error::Invalid {
+                path: path.clone(),
+                name,
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Whether bookmarks can be added to the file at `path` with [`append`], which only writes TOML.
+pub fn can_append(path: impl AsRef<Path>) -> bool {
+    !matches!(
+        extension(path.as_ref()).as_deref(),
+        Some("kfr" | "par" | "png")
+    )
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_ascii_lowercase())
+}
+
+/// Add `bookmark` to the end of the file at `path`, creating it if it doesn't exist. The rest
+/// of the file is left as it is, comments and all.
+pub fn append(path: impl AsRef<Path>, bookmark: &Bookmark) -> Result<(), Error> {
+    let path = path.as_ref().to_path_buf();
+    let io_error = |err| Error::Io {
+        path: path.clone(),
+        err,
+    };
+
+    let text = toml::to_string(&BookmarksFile {
+        bookmarks: vec![bookmark.to_entry()],
+    })
+    .map_err(Error::Serialize)?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(io_error)?;
+    let separator = if file.metadata().map_err(io_error)?.len() > 0 {
+        "\n"
+    } else {
+        ""
+    };
+    write!(file, "{}{}", separator, text).map_err(io_error)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        err: std::io::Error,
+    },
+    Toml {
+        path: PathBuf,
+        err: toml::de::Error,
+    },
+    Invalid {
+        path: PathBuf,
+        name: String,
+        reason: String,
+    },
+    Serialize(toml::ser::Error),
+    Kfr {
+        path: PathBuf,
+        err: ParseKfrError,
+    },
+    Par {
+        path: PathBuf,
+        err: ParseParError,
+    },
+    Scene(scene::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io { path, err } => write!(f, "failed to access {}: {}", path.display(), err),
+            Error::Toml { path, err } => {
+                write!(f, "invalid bookmarks in {}: {}", path.display(), err)
+            }
+            Error::Invalid { path, name, reason } => write!(
+                f,
+                "invalid bookmark {:?} in {}: {}",
+                name,
+                path.display(),
+                reason
+            ),
+            Error::Serialize(err) => write!(f, "failed to write bookmark: {}", err),
+            Error::Kfr { path, err } => {
+                write!(
+                    f,
+                    "invalid Kalles Fraktaler file {}: {}",
+                    path.display(),
+                    err
+                )
+            }
+            Error::Par { path, err } => {
+                write!(f, "invalid Fractint file {}: {}", path.display(), err)
+            }
+            Error::Scene(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, TemporaryFile};
+
+    #[test]
+    fn appended_bookmarks_load_back() {
+        let file = TemporaryFile::new("bookmarks", "round-trip.toml");
+        let full = Bookmark {
+            name: "Seahorse valley".to_string(),
+            view: "-0.74364388703715870475219150 0.13182590420531197049 2e20"
+                .parse()
+                .unwrap(),
+            formula: Some(Formula::BurningShip),
+            exponent: Some(3.5),
+            iteration_limit: Some(4096),
+            palette: Some(Palette::Fire),
+        };
+        let view_only = Bookmark {
+            name: "Home".to_string(),
+            view: View::default(),
+            formula: None,
+            exponent: None,
+            iteration_limit: None,
+            palette: None,
+        };
+        append(file.path(), &full).unwrap();
+        append(file.path(), &view_only).unwrap();
+
+        let bookmarks = load(file.path()).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        for (loaded, saved) in bookmarks.iter().zip([&full, &view_only]) {
+            assert_eq!(loaded.name, saved.name);
+            assert_eq!(loaded.view, saved.view);
+            assert_eq!(loaded.formula, saved.formula);
+            assert_eq!(loaded.exponent, saved.exponent);
+            assert_eq!(loaded.iteration_limit, saved.iteration_limit);
+            assert_eq!(loaded.palette, saved.palette);
+        }
+    }
+
+    #[test]
+    fn invalid_bookmarks_are_rejected() {
+        let sources = [
+            "zoom = 0.0",
+            "zoom = -1.0",
+            "zoom = 1.0\niteration-limit = 0",
+            "zoom = 1.0\nexponent = 0.5",
+            "zoom = 1.0\nexponent = -2.0",
+            "zoom = 1.0\nexponent = nan",
+            "zoom = 1.0\nexponent = inf",
+        ]
+        .map(|settings| {
+            format!(
+                "[[bookmark]]\nname = \"a\"\nreal = \"0\"\nimaginary = \"0\"\n{}\n",
+                settings
+            )
+        });
+        test_support::assert_rejected(
+            &TemporaryFile::new("bookmarks", "invalid.toml"),
+            sources,
+            |path| load(path),
+            |err| matches!(err, Error::Invalid { .. }),
+        );
+    }
+}
@@ -0,0 +1,136 @@
+/*!
+Command-line arguments.
+
+Without a subcommand, the arguments are the same as `view`'s, so that `wgpu-mandelbrot
+bookmarks.toml` opens a window.
+*/
+
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+use dashu_float::DBig;
+use wgpu_mandelbrot::{colour::Palette, formula::Formula, screen, view::View};
+
+/// Render the Mandelbrot set and other escape-time fractals on the GPU.
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub mode: Option<Mode>,
+    #[command(flatten)]
+    pub view: ViewArgs,
+}
+
+#[derive(Subcommand)]
+pub enum Mode {
+    /// Explore in a window (the default).
+    View(ViewArgs),
+    /// Render an image without a window, and save it as a PNG.
+    Render(RenderArgs),
+    /// Render without a window, and report how long it took.
+    Bench(BenchArgs),
+}
+
+/// What to render, which is shared by every mode.
+#[derive(Args)]
+pub struct SceneArgs {
+    /// A scene file to start from, which the other options override
+    #[arg(long)]
+    pub scene: Option<PathBuf>,
+    /// The real part of the center [default: from the scene, WGPU_MANDELBROT_VIEW, or -0.74529]
+    #[arg(long, allow_hyphen_values = true)]
+    pub real: Option<DBig>,
+    /// The imaginary part of the center [default: from the scene, WGPU_MANDELBROT_VIEW, or
+    /// 0.113075]
+    #[arg(long, allow_hyphen_values = true)]
+    pub imaginary: Option<DBig>,
+    /// How far to zoom in, where 1 shows from -2 to 2 vertically [default: from the scene,
+    /// WGPU_MANDELBROT_VIEW, or 1]
+    #[arg(long)]
+    pub zoom: Option<f64>,
+    /// Pixels stop being iterated when they reach this many iterations [default: from the scene,
+    /// or 65536]
+    #[arg(long)]
+    pub iteration_limit: Option<u32>,
+    /// The formula to iterate, such as `multibrot` or `burning-ship` [default: from the scene,
+    /// multibrot, or custom with WGPU_MANDELBROT_FORMULA_FILE]
+    #[arg(long)]
+    pub formula: Option<Formula>,
+    /// The palette to colour with: green, fire, ocean or grayscale [default: from the scene, or
+    /// green]
+    #[arg(long)]
+    pub palette: Option<Palette>,
+}
+
+impl SceneArgs {
+    /// `view` with any parts that were given on the command line replaced.
+    pub fn apply_to(&self, view: View) -> Result<View, String> {
+        if let Some(zoom) = self.zoom {
+            if !(zoom.is_finite() && zoom > 0.0) {
+                return Err(format!("invalid zoom: {} (must be positive)", zoom));
+            }
+        }
+        if self.iteration_limit == Some(0) {
+            return Err("invalid iteration limit: 0 (must be positive)".to_string());
+        }
+
+        Ok(View::new(
+            self.real.clone().unwrap_or_else(|| view.real().clone()),
+            self.imaginary
+                .clone()
+                .unwrap_or_else(|| view.imaginary().clone()),
+            self.zoom.unwrap_or(view.zoom()),
+        ))
+    }
+}
+
+#[derive(Args)]
+pub struct ViewArgs {
+    #[command(flatten)]
+    pub scene: SceneArgs,
+    /// The size of the window, as `<width>x<height>` [default: from the scene]
+    #[arg(long)]
+    pub size: Option<screen::Size>,
+    /// Start in fullscreen
+    #[arg(long)]
+    pub fullscreen: bool,
+    /// Show the render progress on a line of the terminal, which is rewritten as it changes (only
+    /// when stderr is a terminal)
+    #[arg(long)]
+    pub status_line: bool,
+    /// Bookmark files to load, of which new bookmarks are saved to the first TOML file [default:
+    /// bookmarks.toml]
+    pub bookmarks: Vec<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+    #[command(flatten)]
+    pub scene: SceneArgs,
+    /// The size of the image, as `<width>x<height>` [default: from the scene, or 1920x1080]
+    #[arg(long)]
+    pub size: Option<screen::Size>,
+    /// How many pixels to compute along each side of a pixel of the image, and average [default:
+    /// from the scene, or 1]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub supersampling: Option<u32>,
+    /// Where to save the image
+    #[arg(short, long, default_value = "wgpu-mandelbrot.png")]
+    pub output: PathBuf,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    #[command(flatten)]
+    pub scene: SceneArgs,
+    /// The size of the image, as `<width>x<height>` [default: from the scene, or 1920x1080]
+    #[arg(long)]
+    pub size: Option<screen::Size>,
+    /// How many pixels to compute along each side of a pixel of the image, and average [default:
+    /// from the scene, or 1]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub supersampling: Option<u32>,
+    /// Stop after this many frames, even if some pixels are still being iterated
+    #[arg(long)]
+    pub frames: Option<u64>,
+}
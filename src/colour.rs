@@ -1,11 +1,16 @@
 //! Colouring algorithms.
 
+use std::{fmt::Display, str::FromStr};
+
 use bytemuck::{Pod, Zeroable};
 use fnv::{FnvHashMap, FnvHashSet};
 use log::trace;
 use rayon::prelude::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
 
-use crate::{pixel::Pixel, screen};
+use crate::{
+    pixel::{Complex, Pixel},
+    screen,
+};
 
 /// [`bytemuck`]-compatible colour output for a single pixel.
 #[repr(C)]
@@ -13,6 +18,9 @@ use crate::{pixel::Pixel, screen};
 pub struct ColourRange {
     pub escaped: u32,
     pub value: f32,
+    /// `0` when the pixel isn't coloured by root, otherwise one more than the index of the
+    /// root that the pixel converged to.
+    pub root: u32,
 }
 
 impl Default for ColourRange {
@@ -20,16 +28,144 @@ impl Default for ColourRange {
         Self {
             escaped: 0,
             value: 0.0,
+            root: 0,
+        }
+    }
+}
+
+/**
+The gradient that escaped pixels are coloured with, from the fewest iterations to the most.
+
+Corresponds to `render.wgsl#palette`.
+*/
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Palette {
+    /// Dark green to white.
+    #[default]
+    Green = 0,
+    /// Dark red through orange to pale yellow.
+    Fire = 1,
+    /// Navy through blue to pale cyan.
+    Ocean = 2,
+    /// Black to white.
+    Grayscale = 3,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Green,
+        Palette::Fire,
+        Palette::Ocean,
+        Palette::Grayscale,
+    ];
+
+    /// The next palette in [`Palette::ALL`], wrapping around at the end.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|palette| *palette == self)
+            .unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Green => "green",
+            Palette::Fire => "fire",
+            Palette::Ocean => "ocean",
+            Palette::Grayscale => "grayscale",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParsePaletteError(String);
+
+impl Display for ParsePaletteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = Palette::ALL.map(Palette::name);
+        write!(
+            f,
+            "unknown palette: {:?} (expected one of {})",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParsePaletteError {}
+
+/// Parses a palette's [`Palette::name`].
+impl FromStr for Palette {
+    type Err = ParsePaletteError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Palette::ALL
+            .into_iter()
+            .find(|palette| palette.name() == input)
+            .ok_or_else(|| ParsePaletteError(input.to_string()))
+    }
+}
+
+/// How escaped pixels are given their position in the [`Palette`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colouring {
+    /// By the fraction of escaped pixels that escaped in fewer iterations, using
+    /// [`HistogramColouring`].
+    #[default]
+    Histogram,
+}
+
+impl Colouring {
+    pub const ALL: [Colouring; 1] = [Colouring::Histogram];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Colouring::Histogram => "histogram",
         }
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseColouringError(String);
+
+impl Display for ParseColouringError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = Colouring::ALL.map(Colouring::name);
+        write!(
+            f,
+            "unknown colouring: {:?} (expected one of {})",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseColouringError {}
+
+/// Parses a colouring's [`Colouring::name`].
+impl FromStr for Colouring {
+    type Err = ParseColouringError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Colouring::ALL
+            .into_iter()
+            .find(|colouring| colouring.name() == input)
+            .ok_or_else(|| ParseColouringError(input.to_string()))
+    }
+}
+
+/// How close (relative to the root's size) a pixel must be to a root to be coloured by it.
+const ROOT_TOLERANCE: f32 = 1e-3;
+
 /// Histogram-based colouring algorithm ([Wikipedia](https://en.wikipedia.org/wiki/Plotting_algorithms_for_the_Mandelbrot_set#Histogram_coloring)).
 pub struct HistogramColouring {
     total_samples: usize,
     bucket_labels: Vec<u32>,
     histogram: FnvHashMap<u32, u32>,
     histogram_ranges: FnvHashMap<u32, f32>,
+    roots: Vec<Complex>,
 }
 
 impl HistogramColouring {
@@ -43,9 +179,29 @@ impl HistogramColouring {
             bucket_labels,
             histogram,
             histogram_ranges,
+            roots: Vec::new(),
         }
     }
 
+    /**
+    Also colour pixels by the root they converged to (see [`ColourRange::root`]).
+
+    When `roots` is empty, pixels are coloured by iteration count alone.
+    */
+    pub fn set_roots(&mut self, roots: Vec<Complex>) {
+        self.roots = roots;
+    }
+
+    fn root_label(&self, value: Complex) -> u32 {
+        self.roots
+            .iter()
+            .enumerate()
+            .map(|(index, root)| (index, (value - *root).length(), root.length()))
+            .filter(|(_, distance, root_length)| *distance < ROOT_TOLERANCE * root_length.max(1.0))
+            .min_by(|(_, a, _), (_, b, _)| a.total_cmp(b))
+            .map_or(0, |(index, _, _)| index as u32 + 1)
+    }
+
     pub fn reset(&mut self) {
         self.total_samples = 0;
         self.bucket_labels.clear();
@@ -71,8 +227,10 @@ impl HistogramColouring {
             for pixel in newly_escaped_pixels {
                 debug_assert!(pixel.escaped == 1);
 
-                colour_ranges[pixel.y as usize * screen_size.width as usize + pixel.x as usize]
-                    .escaped = 1;
+                let colour_range = &mut colour_ranges
+                    [pixel.y as usize * screen_size.width as usize + pixel.x as usize];
+                colour_range.escaped = 1;
+                colour_range.root = self.root_label(pixel.current_value);
 
                 let value = self
                     .histogram
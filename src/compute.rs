@@ -1,5 +1,179 @@
 //! Compute shader functions.
 
+use crate::{formula::Formula, screen, view::View};
+
+/// The source of `compute.wgsl`.
+pub const SHADER_SOURCE: &str = include_str!("compute.wgsl");
+
+/// The source of `compute_f64.wgsl`, which requires [`wgpu::Features::SHADER_FLOAT64`].
+pub const F64_SHADER_SOURCE: &str = include_str!("compute_f64.wgsl");
+
+/// The ways that `compute.wgsl` can iterate pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// Iterate each pixel's value in `f32` (`compute.wgsl#mandelbrot`).
+    #[default]
+    Direct,
+
+    /**
+    Iterate each pixel's value in double-single precision (`compute.wgsl#double_single`), for
+    zooms up to about `1e12`. See [`crate::double_single`].
+
+    Only supports the escape-time formulas from [`Formula::Multibrot`] to
+    [`Formula::CelticBurningShip`], with integer exponents of at least 2.
+    */
+    DoubleSingle,
+
+    /**
+    Iterate each pixel's value in `f64` (`compute_f64.wgsl#mandelbrot_f64`), for zooms up to
+    about `1e13`.
+
+    Requires [`wgpu::Features::SHADER_FLOAT64`], and supports the same formulas as
+    [`Engine::DoubleSingle`].
+    */
+    Float64,
+
+    /**
+    Iterate each pixel's difference from a reference orbit (`compute.wgsl#perturbation`), for
+    deep zooms. See [`crate::perturbation`].
+
+    Only supports the Mandelbrot set.
+    */
+    Perturbation,
+}
+
+impl Engine {
+    pub const ALL: [Engine; 4] = [
+        Engine::Direct,
+        Engine::DoubleSingle,
+        Engine::Float64,
+        Engine::Perturbation,
+    ];
+
+    /// The next engine in [`Engine::ALL`], wrapping around at the end.
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|engine| *engine == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Direct => "direct",
+            Engine::DoubleSingle => "double-single",
+            Engine::Float64 => "f64",
+            Engine::Perturbation => "perturbation",
+        }
+    }
+
+    /// A description of the precision that the engine iterates pixels in.
+    pub fn precision(self) -> &'static str {
+        match self {
+            Engine::Direct => "f32",
+            Engine::DoubleSingle => "pairs of f32",
+            Engine::Float64 => "f64",
+            Engine::Perturbation => "f32, relative to an arbitrary-precision reference",
+        }
+    }
+
+    /// The device features that the engine needs.
+    pub fn required_features(self) -> wgpu::Features {
+        match self {
+            Engine::Float64 => wgpu::Features::SHADER_FLOAT64,
+            _ => wgpu::Features::empty(),
+        }
+    }
+
+    /// The entry point that implements the engine, in `compute.wgsl` or `compute_f64.wgsl`.
+    pub fn entry_point(self) -> &'static str {
+        match self {
+            Engine::Direct => "mandelbrot",
+            Engine::DoubleSingle => "double_single",
+            Engine::Float64 => "mandelbrot_f64",
+            Engine::Perturbation => "perturbation",
+        }
+    }
+
+    /// Whether the engine can render `formula` raised to `exponent`.
+    pub fn supports(self, formula: Formula, exponent: f32) -> bool {
+        match self {
+            Engine::Direct => true,
+            Engine::DoubleSingle | Engine::Float64 => {
+                matches!(
+                    formula,
+                    Formula::Multibrot
+                        | Formula::BurningShip
+                        | Formula::Tricorn
+                        | Formula::Celtic
+                        | Formula::CelticMandelbar
+                        | Formula::CelticBurningShip
+                ) && exponent >= 2.0
+                    && exponent.fract() == 0.0
+            }
+            Engine::Perturbation => formula == Formula::Multibrot && exponent == 2.0,
+        }
+    }
+
+    /**
+    Whether the engine's numbers are precise enough to tell neighbouring pixels apart when
+    `view` is shown on a screen of `size`. If they aren't, the image turns into blocks.
+    */
+    pub fn resolves(self, view: &View, size: screen::Size) -> bool {
+        // Neighbouring pixels need a few bits that differ, or their orbits are barely distinct.
+        const SPARE_BITS: i32 = 3;
+
+        let epsilon = match self {
+            Engine::Direct => f32::EPSILON as f64,
+            Engine::DoubleSingle => (f32::EPSILON as f64).powi(2),
+            Engine::Float64 => f64::EPSILON,
+            // Only differences from the reference point are iterated, so the magnitude of the
+            // coordinates doesn't matter, as long as the pixel spacing is a normal `f64`.
+            Engine::Perturbation => return view.pixel_spacing(size).is_normal(),
+        };
+
+        view.pixel_spacing(size) >= view.magnitude() * epsilon * 2.0_f64.powi(SPARE_BITS)
+    }
+
+    /**
+    The engine to use for `formula` raised to `exponent`, out of the engines that need no more
+    than `features`.
+
+    This is `self` if it resolves `view` on a screen of `size`, or else the first engine after
+    it (in [`Engine::ALL`]) that does. [`Engine::Direct`] is used instead of `self` if `self`
+    doesn't support the formula. If no engine resolves the view, this is the last engine
+    considered, which is the most precise.
+    */
+    pub fn select(
+        self,
+        formula: Formula,
+        exponent: f32,
+        features: wgpu::Features,
+        view: &View,
+        size: screen::Size,
+    ) -> Engine {
+        let first = if self.supports(formula, exponent) {
+            self
+        } else {
+            Engine::Direct
+        };
+
+        let candidates = Engine::ALL
+            .into_iter()
+            .skip_while(|&engine| engine != first)
+            .filter(|&engine| {
+                features.contains(engine.required_features()) && engine.supports(formula, exponent)
+            });
+
+        let mut selected = first;
+        for engine in candidates {
+            selected = engine;
+            if engine.resolves(view, size) {
+                break;
+            }
+        }
+        selected
+    }
+}
+
 /// Workgroup size for `compute.wsgl#mandelbrot`.
 pub const MANDELBROT_WORKGROUP_SIZE_Y: u32 = 64;
 
@@ -76,3 +250,101 @@ pub fn mandelbrot_dispatch_size(total_work: usize) -> (u32, u32, u32) {
         .unwrap();
     (x, MANDELBROT_DISPATCH_SIZE_Y, 1)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: screen::Size = screen::Size {
+        width: 1000,
+        height: 1000,
+    };
+
+    /**
+    The zoom past which the default view's pixels are closer together than `2^3` units in the
+    last place of `epsilon`, when `epsilon` is relative to the largest coordinate on screen.
+    */
+    fn limit(epsilon: f64) -> f64 {
+        let real = View::default().real().to_f64().value().abs();
+        let spacing = epsilon * 8.0;
+        // Solves `4 / (zoom * width) = (real + 2 / zoom) * spacing` for `zoom`.
+        (4.0 / SIZE.width as f64 - 2.0 * spacing) / (real * spacing)
+    }
+
+    #[test]
+    fn engines_stop_resolving_at_their_precision() {
+        for (engine, limit) in [
+            (Engine::Direct, limit(f32::EPSILON as f64)),
+            (Engine::DoubleSingle, limit((f32::EPSILON as f64).powi(2))),
+            (Engine::Float64, limit(f64::EPSILON)),
+            // Where the pixel spacing becomes subnormal.
+            (
+                Engine::Perturbation,
+                4.0 / (SIZE.width as f64 * f64::MIN_POSITIVE),
+            ),
+        ] {
+            let mut view = View::default();
+            view.set_zoom(limit * 0.99);
+            assert!(engine.resolves(&view, SIZE), "{:?} at {}", engine, limit);
+            view.set_zoom(limit * 1.01);
+            assert!(!engine.resolves(&view, SIZE), "{:?} at {}", engine, limit);
+        }
+    }
+
+    #[test]
+    fn engines_are_selected_in_order_of_precision() {
+        for (features, expected) in [
+            (
+                wgpu::Features::SHADER_FLOAT64,
+                &[
+                    Engine::Direct,
+                    Engine::DoubleSingle,
+                    Engine::Float64,
+                    Engine::Perturbation,
+                ][..],
+            ),
+            (
+                wgpu::Features::empty(),
+                &[Engine::Direct, Engine::DoubleSingle, Engine::Perturbation],
+            ),
+        ] {
+            let mut selected = Vec::new();
+            let mut view = View::default();
+            for exponent in 0..=300 {
+                view.set_zoom(10.0_f64.powi(exponent));
+                let engine = Engine::Direct.select(Formula::Multibrot, 2.0, features, &view, SIZE);
+                assert!(
+                    engine.resolves(&view, SIZE),
+                    "{:?} at {}",
+                    engine,
+                    view.zoom()
+                );
+                if selected.last() != Some(&engine) {
+                    selected.push(engine);
+                }
+            }
+            assert_eq!(selected, expected, "{:?}", features);
+        }
+    }
+
+    #[test]
+    fn engines_are_selected_at_their_limits() {
+        let features = wgpu::Features::SHADER_FLOAT64;
+        for (zoom, expected) in [
+            (limit(f32::EPSILON as f64) * 0.99, Engine::Direct),
+            (limit(f32::EPSILON as f64) * 1.01, Engine::DoubleSingle),
+            (
+                limit((f32::EPSILON as f64).powi(2)) * 0.99,
+                Engine::DoubleSingle,
+            ),
+            (limit((f32::EPSILON as f64).powi(2)) * 1.01, Engine::Float64),
+            (limit(f64::EPSILON) * 0.99, Engine::Float64),
+            (limit(f64::EPSILON) * 1.01, Engine::Perturbation),
+        ] {
+            let mut view = View::default();
+            view.set_zoom(zoom);
+            let engine = Engine::Direct.select(Formula::Multibrot, 2.0, features, &view, SIZE);
+            assert_eq!(engine, expected, "{}", zoom);
+        }
+    }
+}
@@ -18,11 +18,180 @@ fn add_complex(first: Complex, second: Complex) -> Complex {
   return Complex(first.real + second.real, first.imaginary + second.imaginary);
 }
 
+fn subtract_complex(first: Complex, second: Complex) -> Complex {
+  return Complex(first.real - second.real, first.imaginary - second.imaginary);
+}
+
+fn divide_complex(first: Complex, second: Complex) -> Complex {
+  let denominator = second.real * second.real + second.imaginary * second.imaginary;
+  return Complex(
+    (first.real * second.real + first.imaginary * second.imaginary) / denominator,
+    (first.imaginary * second.real - first.real * second.imaginary) / denominator
+  );
+}
+
 fn length_complex(value: Complex) -> f32 {
   return sqrt(pow(value.real, 2.0) + pow(value.imaginary, 2.0));
 }
 
+fn conjugate_complex(value: Complex) -> Complex {
+  return Complex(value.real, -value.imaginary);
+}
+
+// Take the absolute value of each component.
+fn abs_complex(value: Complex) -> Complex {
+  return Complex(abs(value.real), abs(value.imaginary));
+}
+
+// Take the absolute value of the real component.
+fn abs_real_complex(value: Complex) -> Complex {
+  return Complex(abs(value.real), value.imaginary);
+}
+
+/*
+Raise `value` to a real `exponent`.
+
+Integer exponents >= 2 use repeated `multiply_complex`, which avoids the precision
+lost by going through `atan2` and `pow`. Every other exponent (fractional or negative)
+uses the polar form `r^n * (cos(n * theta) + i * sin(n * theta))`.
+
+Zero is mapped to zero for every exponent, so that negative exponents don't produce an
+infinity on the first iteration (when `z = 0`).
+*/
+fn power_complex(value: Complex, exponent: f32) -> Complex {
+  if exponent >= 2.0 && fract(exponent) == 0.0 {
+    var result = value;
+    for (var i = 1u; i < u32(exponent); i++) {
+      result = multiply_complex(result, value);
+    }
+    return result;
+  }
+
+  let r = length_complex(value);
+  if r == 0.0 {
+    return ZERO_COMPLEX;
+  }
+
+  let theta = atan2(value.imaginary, value.real);
+  let r_n = pow(r, exponent);
+  return Complex(r_n * cos(exponent * theta), r_n * sin(exponent * theta));
+}
+
 let ESCAPE_THRESHOLD: f32 = 2.0;
+let MAGNET_ESCAPE_THRESHOLD: f32 = 100.0;
+
+/*
+A pixel has converged when a step moves it less than this, relative to its size.
+
+Relative, because the spacing between `f32`s grows with their magnitude. Roots far from the
+origin would never converge under an absolute threshold.
+*/
+let CONVERGENCE_THRESHOLD: f32 = 1e-5;
+
+fn has_converged(previous_value: Complex, value: Complex) -> bool {
+  return
+    length_complex(subtract_complex(value, previous_value)) <
+    CONVERGENCE_THRESHOLD * max(1.0, length_complex(value));
+}
+
+/*
+A "double-single" number `high + low`, which has about twice the precision of an `f32`.
+
+`|low|` is at most half of the spacing between `f32`s near `high`. The algorithms are Dekker's
+and Knuth's error-free transformations, as described in Andrew Thall's "Extended-Precision
+Floating-Point Numbers for GPU Computation". They assume that the shader compiler doesn't
+reassociate floating point operations.
+*/
+struct DoubleSingle{high: f32, low: f32}
+
+struct DoubleSingleComplex{real: DoubleSingle, imaginary: DoubleSingle}
+
+// `first + second`, where `first` is at least as large as `second`.
+fn quick_two_sum(first: f32, second: f32) -> DoubleSingle {
+  let sum = first + second;
+  return DoubleSingle(sum, second - (sum - first));
+}
+
+// `first + second`, exactly.
+fn two_sum(first: f32, second: f32) -> DoubleSingle {
+  let sum = first + second;
+  let second_virtual = sum - first;
+  let first_virtual = sum - second_virtual;
+  return DoubleSingle(sum, (first - first_virtual) + (second - second_virtual));
+}
+
+// Split an `f32` into two halves that can be multiplied without rounding.
+fn split_f32(value: f32) -> vec2<f32> {
+  // `2^12 + 1`
+  let scaled = 4097.0 * value;
+  let high = scaled - (scaled - value);
+  return vec2<f32>(high, value - high);
+}
+
+// `first * second`, exactly.
+fn two_product(first: f32, second: f32) -> DoubleSingle {
+  let product = first * second;
+  let first_split = split_f32(first);
+  let second_split = split_f32(second);
+  let error =
+    ((first_split.x * second_split.x - product) + first_split.x * second_split.y + first_split.y * second_split.x) +
+    first_split.y * second_split.y;
+  return DoubleSingle(product, error);
+}
+
+fn add_double_single(first: DoubleSingle, second: DoubleSingle) -> DoubleSingle {
+  let high = two_sum(first.high, second.high);
+  let low = two_sum(first.low, second.low);
+  let sum = quick_two_sum(high.high, high.low + low.high);
+  return quick_two_sum(sum.high, sum.low + low.low);
+}
+
+fn negate_double_single(value: DoubleSingle) -> DoubleSingle {
+  return DoubleSingle(-value.high, -value.low);
+}
+
+fn multiply_double_single(first: DoubleSingle, second: DoubleSingle) -> DoubleSingle {
+  let product = two_product(first.high, second.high);
+  return quick_two_sum(product.high, product.low + (first.high * second.low + first.low * second.high));
+}
+
+// `numerator / denominator` for `f32`s, to double-single precision.
+fn divide_f32_double_single(numerator: f32, denominator: f32) -> DoubleSingle {
+  let quotient = numerator / denominator;
+  let product = two_product(quotient, denominator);
+  let remainder = (numerator - product.high) - product.low;
+  return quick_two_sum(quotient, remainder / denominator);
+}
+
+fn abs_double_single(value: DoubleSingle) -> DoubleSingle {
+  if value.high < 0.0 {
+    return negate_double_single(value);
+  }
+  return value;
+}
+
+fn add_double_single_complex(first: DoubleSingleComplex, second: DoubleSingleComplex) -> DoubleSingleComplex {
+  return DoubleSingleComplex(
+    add_double_single(first.real, second.real),
+    add_double_single(first.imaginary, second.imaginary)
+  );
+}
+
+fn multiply_double_single_complex(first: DoubleSingleComplex, second: DoubleSingleComplex) -> DoubleSingleComplex {
+  let a = multiply_double_single(first.real, second.real);
+  let b = add_double_single(
+    multiply_double_single(first.real, second.imaginary),
+    multiply_double_single(first.imaginary, second.real)
+  );
+  let c = multiply_double_single(first.imaginary, second.imaginary);
+
+  return DoubleSingleComplex(add_double_single(a, negate_double_single(c)), b);
+}
+
+// The `high` parts of each component.
+fn high_complex(value: DoubleSingleComplex) -> Complex {
+  return Complex(value.real.high, value.imaginary.high);
+}
 
 struct Pixel{
   x : u32,
@@ -30,6 +199,75 @@ struct Pixel{
   escaped : u32,
   current_value : Complex,
   iteration_count : u32,
+  // The value before `current_value`, for `FORMULA_PHOENIX`.
+  previous_value : Complex,
+  // State that belongs to the entry point that iterates the pixel, such as
+  // `PerturbationState` or the low parts of a `DoubleSingleComplex`.
+  engine_state : vec4<u32>,
+}
+
+// `mantissa * 2^exponent`, for numbers outside the range of `f32`.
+struct Scaled{mantissa: f32, exponent: i32}
+
+/*
+Approximates every pixel's `dz` after `iterations` iterations as
+`a * u + b * u^2 + c * u^3 * 2^exponent`, where `u = dc / scale`.
+
+Corresponds to `perturbation.rs#SeriesApproximation`.
+*/
+struct SeriesApproximation{
+  a: vec2<f32>,
+  b: vec2<f32>,
+  c: vec2<f32>,
+  exponent: i32,
+  iterations: u32,
+}
+
+// A complex number `value * 2^exponent`.
+struct ScaledComplex{value: Complex, exponent: i32}
+
+/*
+The `Pixel.engine_state` of the `perturbation` entry point. `current_value` is
+`reference_orbit[reference_iteration] + delta`.
+*/
+struct PerturbationState{
+  delta: ScaledComplex,
+  reference_iteration: u32,
+}
+
+fn load_perturbation_state(engine_state: vec4<u32>) -> PerturbationState {
+  return PerturbationState(
+    ScaledComplex(
+      Complex(bitcast<f32>(engine_state.x), bitcast<f32>(engine_state.y)),
+      bitcast<i32>(engine_state.z)
+    ),
+    engine_state.w
+  );
+}
+
+fn store_perturbation_state(state: PerturbationState) -> vec4<u32> {
+  return vec4<u32>(
+    bitcast<u32>(state.delta.value.real),
+    bitcast<u32>(state.delta.value.imaginary),
+    bitcast<u32>(state.delta.exponent),
+    state.reference_iteration
+  );
+}
+
+// Rescale `scaled.value` to have a largest component in `[1, 2)`, without changing the number it represents.
+fn normalise_scaled_complex(scaled: ScaledComplex) -> ScaledComplex {
+  let magnitude = max(abs(scaled.value.real), abs(scaled.value.imaginary));
+  if magnitude == 0.0 {
+    return scaled;
+  }
+
+  let shift = i32(floor(log2(magnitude)));
+  return ScaledComplex(scale_complex(exp2(f32(-shift)), scaled.value), scaled.exponent + shift);
+}
+
+// Convert to a `Complex`, which is zero if the number is too small for an `f32`.
+fn unscale_complex(scaled: ScaledComplex) -> Complex {
+  return scale_complex(exp2(f32(scaled.exponent)), scaled.value);
 }
 
 /*
@@ -39,6 +277,10 @@ From [Wikipedia](https://en.wikipedia.org/wiki/Mandelbrot_set)
 > does not diverge to infinity when iterated from `z = 0`, i.e., for which the sequence `f_c(0)`,
 > `f_c(f_c(0))`, etc., remains bounded in absolute value.
 
+We generalise this to the [Multibrot sets](https://en.wikipedia.org/wiki/Multibrot_set), which
+iterate `f_c( z ) = z^n + c` for a real exponent `n` (see `exponent`). The Mandelbrot set is the
+Multibrot set for `n = 2`.
+
 We treat each complex number `c = a + bi` as a pixel with coordinates `(a, b)`.
 
 Each pixel's membership in the Mandelbrot set depends only on that pixel. This is a perfect
@@ -57,12 +299,224 @@ on the screen, and sample the results texture for its color.
 // View the set from `(-(2 / zoom), -(2 / zoom))` to `(2 / zoom, 2 / zoom)`
 @group(0) @binding(1) var<uniform> zoom : f32;
 
-// Center the image on `origin`,
-@group(0) @binding(2) var<uniform> origin : vec2<f32>;
+// Center the image on `origin.xy + origin.zw`: the high and low parts of a `DoubleSingleComplex`.
+// Only the `double_single` entry point uses the low parts.
+@group(0) @binding(2) var<uniform> origin : vec4<f32>;
+
+// The exponent `n` in `z^n + c`.
+@group(0) @binding(3) var<uniform> exponent : f32;
+
+// One of the `FORMULA_*` constants.
+@group(0) @binding(4) var<uniform> formula : u32;
+
+// The polynomial for `FORMULA_NEWTON` and `FORMULA_NOVA`, from the lowest degree
+// coefficient to the highest. Corresponds to `formula.rs#Polynomial`.
+@group(0) @binding(5) var<storage, read> coefficients : array<Complex>;
+
+// The relaxation parameter `R` for `FORMULA_NEWTON` and `FORMULA_NOVA`.
+@group(0) @binding(6) var<uniform> relaxation : Complex;
+
+// The coefficient `p` of the previous value in `FORMULA_PHOENIX`.
+@group(0) @binding(7) var<uniform> phoenix_parameter : Complex;
+
+// `2 / zoom`, for the `perturbation` entry point. `zoom` isn't precise enough at deep zooms.
+@group(0) @binding(8) var<uniform> scale : Scaled;
+
+// Lets the `perturbation` entry point skip the first `series_approximation.iterations` iterations.
+@group(0) @binding(9) var<uniform> series_approximation : SeriesApproximation;
+
+// `2 / zoom`, for the `double_single` entry point.
+@group(0) @binding(10) var<uniform> double_single_scale : DoubleSingle;
 
 @group(1) @binding(0) var<storage, read> input : array<Pixel>;
 @group(1) @binding(1) var<storage, read_write> output : array<Pixel>;
 
+// The orbit of `origin` under `FORMULA_MULTIBROT`, for the `perturbation` entry point. Computed
+// in arbitrary precision by `perturbation.rs#ReferenceOrbit`.
+@group(1) @binding(2) var<storage, read> reference_orbit : array<Complex>;
+
+// Corresponds to `formula.rs#Formula`.
+let FORMULA_MULTIBROT = 0u;
+let FORMULA_BURNING_SHIP = 1u;
+let FORMULA_TRICORN = 2u;
+let FORMULA_CELTIC = 3u;
+let FORMULA_CELTIC_MANDELBAR = 4u;
+let FORMULA_CELTIC_BURNING_SHIP = 5u;
+let FORMULA_NEWTON = 6u;
+let FORMULA_NOVA = 7u;
+let FORMULA_PHOENIX = 8u;
+let FORMULA_MAGNET_ONE = 9u;
+let FORMULA_MAGNET_TWO = 10u;
+let FORMULA_CUSTOM = 11u;
+
+// `((z^2 + c - 1) / (2z + c - 2))^2`
+fn magnet_one_step(value: Complex, c: Complex) -> Complex {
+  let c_minus_1 = subtract_complex(c, Complex(1.0, 0.0));
+  let c_minus_2 = subtract_complex(c, Complex(2.0, 0.0));
+
+  let numerator = add_complex(multiply_complex(value, value), c_minus_1);
+  let denominator = add_complex(scale_complex(2.0, value), c_minus_2);
+
+  let quotient = divide_complex(numerator, denominator);
+  return multiply_complex(quotient, quotient);
+}
+
+// `((z^3 + 3(c - 1)z + (c - 1)(c - 2)) / (3z^2 + 3(c - 2)z + (c - 1)(c - 2) + 1))^2`
+fn magnet_two_step(value: Complex, c: Complex) -> Complex {
+  let c_minus_1 = subtract_complex(c, Complex(1.0, 0.0));
+  let c_minus_2 = subtract_complex(c, Complex(2.0, 0.0));
+  let c_minus_1_times_c_minus_2 = multiply_complex(c_minus_1, c_minus_2);
+  let value_squared = multiply_complex(value, value);
+
+  let numerator = add_complex(
+    add_complex(
+      multiply_complex(value_squared, value),
+      scale_complex(3.0, multiply_complex(c_minus_1, value))
+    ),
+    c_minus_1_times_c_minus_2
+  );
+  let denominator = add_complex(
+    add_complex(
+      scale_complex(3.0, value_squared),
+      scale_complex(3.0, multiply_complex(c_minus_2, value))
+    ),
+    add_complex(c_minus_1_times_c_minus_2, Complex(1.0, 0.0))
+  );
+
+  let quotient = divide_complex(numerator, denominator);
+  return multiply_complex(quotient, quotient);
+}
+
+fn is_magnet(formula: u32) -> bool {
+  return formula == FORMULA_MAGNET_ONE || formula == FORMULA_MAGNET_TWO;
+}
+
+/*
+For `z^n + c` with `n > 1` and `|c| <= 2`, an orbit is guaranteed to diverge once
+`|z| > max(2, 2^(1 / (n - 1)))`. The bound is just `2` for `n >= 2`, but grows quickly as
+`n` approaches `1`.
+
+There is no such bound for `n <= 1`, so we keep the usual threshold of `2`.
+
+The magnet formulas ignore the exponent, and use a much larger threshold to keep them
+distinct from the pixels that converge to `1`.
+*/
+fn escape_threshold(formula: u32, exponent: f32) -> f32 {
+  if is_magnet(formula) {
+    return MAGNET_ESCAPE_THRESHOLD;
+  } else if exponent > 1.0 {
+    return max(ESCAPE_THRESHOLD, pow(2.0, 1.0 / (exponent - 1.0)));
+  } else {
+    return ESCAPE_THRESHOLD;
+  }
+}
+
+/*
+Apply one step of Newton's method to the polynomial `p` in `coefficients`:
+`z - R * p(z) / p'(z)`, where `R` is `relaxation`.
+
+`p(z)` and `p'(z)` are evaluated together using Horner's method.
+*/
+fn newton_step(value: Complex) -> Complex {
+  let degree = arrayLength(&coefficients) - 1u;
+
+  var p = ZERO_COMPLEX;
+  var p_derivative = ZERO_COMPLEX;
+  for (var i = 0u; i <= degree; i++) {
+    p_derivative = add_complex(multiply_complex(p_derivative, value), p);
+    p = add_complex(multiply_complex(p, value), coefficients[degree - i]);
+  }
+
+  return subtract_complex(value, multiply_complex(relaxation, divide_complex(p, p_derivative)));
+}
+
+// Whether `formula` stops iterating when it converges, instead of when it escapes.
+fn converges(formula: u32) -> bool {
+  return formula == FORMULA_NEWTON || formula == FORMULA_NOVA;
+}
+
+// The value of `z` before the first iteration.
+fn initial_value(formula: u32, c: Complex) -> Complex {
+  switch formula {
+    case 6u: { // FORMULA_NEWTON
+      return c;
+    }
+    case 7u: { // FORMULA_NOVA
+      return Complex(1.0, 0.0);
+    }
+    default: {
+      return ZERO_COMPLEX;
+    }
+  }
+}
+
+/*
+The step function for `FORMULA_CUSTOM`.
+
+`custom_formula.rs#CustomFormula` replaces everything between the `BEGIN CUSTOM FORMULA` and
+`END CUSTOM FORMULA` lines with a user-supplied `fn step(z: Complex, c: Complex) -> Complex`,
+renamed to `custom_step` (calls to `step` resolve to the WGSL builtin). It can use any of the
+functions and bindings declared above.
+
+Without a user-supplied formula, it's the Mandelbrot set.
+*/
+// BEGIN CUSTOM FORMULA
+fn custom_step(z: Complex, c: Complex) -> Complex {
+  return add_complex(multiply_complex(z, z), c);
+}
+// END CUSTOM FORMULA
+
+/*
+Apply one iteration of `formula` to `value`.
+
+The escape-time variants mostly differ in how they fold `value` (before raising it to
+`exponent`) and `value^exponent` (before adding `c`). Every formula shares the `Pixel` state
+and colouring, and only `FORMULA_PHOENIX` uses `previous_value`.
+*/
+fn iterate(formula: u32, exponent: f32, value: Complex, previous_value: Complex, c: Complex) -> Complex {
+  switch formula {
+    case 1u: { // FORMULA_BURNING_SHIP
+      return add_complex(power_complex(abs_complex(value), exponent), c);
+    }
+    case 2u: { // FORMULA_TRICORN
+      return add_complex(power_complex(conjugate_complex(value), exponent), c);
+    }
+    case 3u: { // FORMULA_CELTIC
+      return add_complex(abs_real_complex(power_complex(value, exponent)), c);
+    }
+    case 4u: { // FORMULA_CELTIC_MANDELBAR
+      return add_complex(abs_real_complex(power_complex(conjugate_complex(value), exponent)), c);
+    }
+    case 5u: { // FORMULA_CELTIC_BURNING_SHIP
+      return add_complex(abs_real_complex(power_complex(abs_complex(value), exponent)), c);
+    }
+    case 6u: { // FORMULA_NEWTON
+      return newton_step(value);
+    }
+    case 7u: { // FORMULA_NOVA
+      return add_complex(newton_step(value), c);
+    }
+    case 8u: { // FORMULA_PHOENIX
+      return add_complex(
+        add_complex(power_complex(value, exponent), c),
+        multiply_complex(phoenix_parameter, previous_value)
+      );
+    }
+    case 9u: { // FORMULA_MAGNET_ONE
+      return magnet_one_step(value, c);
+    }
+    case 10u: { // FORMULA_MAGNET_TWO
+      return magnet_two_step(value, c);
+    }
+    case 11u: { // FORMULA_CUSTOM
+      return custom_step(value, c);
+    }
+    default: { // FORMULA_MULTIBROT
+      return add_complex(power_complex(value, exponent), c);
+    }
+  }
+}
+
 let MANDELBROT_DISPATCH_SIZE_Y = 1024u;
 let MANDELBROT_WORKGROUP_SIZE_Y = 64u;
 
@@ -85,19 +539,25 @@ fn mandelbrot(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     2.0 * zoom_inv * f32(y) / f32(screen_size.y) - zoom_inv + origin.y
   );
 
-  let starting_value : Complex = pixel.current_value;
+  // Every pixel starts with a `current_value` of zero, but not every formula starts from zero.
+  var starting_value : Complex = pixel.current_value;
+  if pixel.iteration_count == 0u {
+    starting_value = initial_value(formula, c);
+  }
+
+  let next_value = iterate(formula, exponent, starting_value, pixel.previous_value, c);
 
   // conditions:
   // 
-  // length_complex(starting_value) > ESCAPE_THRESHOLD implies xx < 0
-  // length_complex(starting_value) == ESCAPE_THRESHOLD implies xx == 0
-  // length_complex(starting_value) < ESCAPE_THRESHOLD implies xx > 0
+  // length_complex(starting_value) > escape_threshold(formula, exponent) implies xx < 0
+  // length_complex(starting_value) == escape_threshold(formula, exponent) implies xx == 0
+  // length_complex(starting_value) < escape_threshold(formula, exponent) implies xx > 0
   // 
-  // length_complex(starting_value) >= ESCAPE_THRESHOLD implies xx <= 0
+  // length_complex(starting_value) >= escape_threshold(formula, exponent) implies xx <= 0
 
-  let escape_threshold_minus_length = ESCAPE_THRESHOLD - length_complex(starting_value);
+  let escape_threshold_minus_length = escape_threshold(formula, exponent) - length_complex(starting_value);
 
-  // length_complex(starting_value) >= ESCAPE_THRESHOLD implies max(xx, 0.0) == 0
+  // length_complex(starting_value) >= escape_threshold(formula, exponent) implies max(xx, 0.0) == 0
   //
   // If `escape_threshold_minus_length` is negative, then `max` outputs `0.0`.
   let escape_threshold_minus_length_max_0 = max(escape_threshold_minus_length, 0.0);
@@ -119,8 +579,20 @@ fn mandelbrot(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
   //   escaped == u32(1.0 - 1.0)
   //   escaped == u32(0.0)
   //   escaped == 0u
-  output[index].escaped =
-    1u - u32(sign(escape_threshold_minus_length_max_0));
+  //
+  // Formulas that converge are finished when they stop moving, rather than when they cross
+  // the threshold.
+  if converges(formula) {
+    output[index].escaped = u32(has_converged(starting_value, next_value));
+  } else {
+    output[index].escaped =
+      1u - u32(sign(escape_threshold_minus_length_max_0));
+  }
+
+  // The magnet formulas are also finished when they converge to their fixed point at `1`.
+  if is_magnet(formula) && has_converged(Complex(1.0, 0.0), starting_value) {
+    output[index].escaped = 1u;
+  }
 
   let escaped_last_iteration : u32 = pixel.escaped;
   output[index].iteration_count =
@@ -136,9 +608,16 @@ fn mandelbrot(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
     (1u - escaped_last_iteration);
 
   let escaped_last_iteration : f32 = f32(escaped_last_iteration);
+
+  // Advance `previous_value` alongside `current_value` (below).
+  output[index].previous_value = add_complex(
+    scale_complex(escaped_last_iteration, pixel.previous_value),
+    scale_complex(1.0 - escaped_last_iteration, starting_value)
+  );
+
   output[index].current_value =
     // set to `starting_value` when the point has escaped.
-    // set to `add_complex(multiply_complex(starting_value, starting_value), c)`
+    // set to `next_value`
     add_complex(
       // escaped == 1u implies
       //   scale_complex(1.0, starting_value),
@@ -149,14 +628,187 @@ fn mandelbrot(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
       //   Complex(0.0, 0.0),
       scale_complex(escaped_last_iteration, starting_value),
       // escaped == 1u implies
-      //   scale_complex(1.0 - 1.0, add_complex(multiply_complex(starting_value, starting_value), c))
-      //   scale_complex(0.0, add_complex(multiply_complex(starting_value, starting_value), c))
+      //   scale_complex(1.0 - 1.0, next_value)
+      //   scale_complex(0.0, next_value)
       //   Complex(0.0, 0.0)
       //
       // escaped == 0u implies
-      //   scale_complex(1.0 - 0.0, add_complex(multiply_complex(starting_value, starting_value), c))
-      //   scale_complex(1.0, add_complex(multiply_complex(starting_value, starting_value), c))
-      //   add_complex(multiply_complex(starting_value, starting_value), c)
-      scale_complex(1.0 - escaped_last_iteration, add_complex(multiply_complex(starting_value, starting_value), c))
+      //   scale_complex(1.0 - 0.0, next_value)
+      //   scale_complex(1.0, next_value)
+      //   next_value
+      scale_complex(1.0 - escaped_last_iteration, next_value)
+    );
+}
+
+/*
+Iterate the Mandelbrot set using perturbation theory (see `perturbation.rs`).
+
+Instead of `z`, each pixel stores its difference `dz` from the reference orbit `Z`, which is
+iterated as `dz' = 2 * Z * dz + dz^2 + dc`. `dc`, the pixel's difference from `origin`, is
+`offset * scale` where `offset` is in `[-1, 1]`.
+
+`dz` and `dc` are often too small for `f32`, so they're stored as `ScaledComplex`s. `dz` is
+kept in units of `2^delta_exponent`:
+
+  dz' / 2^e = 2 * Z * (dz / 2^e) + 2^e * (dz / 2^e)^2 + 2^(scale.exponent - e) * (dc / 2^scale.exponent)
+
+When `2^e` underflows, `dz^2` is negligible.
+*/
+@compute @workgroup_size(1, 64, 1)
+fn perturbation(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+  let index = global_invocation_id.x * (MANDELBROT_DISPATCH_SIZE_Y * MANDELBROT_WORKGROUP_SIZE_Y) + global_invocation_id.y;
+
+  let pixel = input[index];
+  output[index].x = pixel.x;
+  output[index].y = pixel.y;
+
+  let offset = Complex(
+    2.0 * f32(pixel.x) / f32(screen_size.x) - 1.0,
+    2.0 * f32(pixel.y) / f32(screen_size.y) - 1.0
+  );
+  let delta_c = scale_complex(scale.mantissa, offset);
+
+  let state = load_perturbation_state(pixel.engine_state);
+  var delta = state.delta;
+  var reference_iteration = state.reference_iteration;
+  var iteration_count = pixel.iteration_count;
+  if iteration_count == 0u {
+    // `a * u + b * u^2 + c * u^3`, using Horner's method.
+    let a = Complex(series_approximation.a.x, series_approximation.a.y);
+    let b = Complex(series_approximation.b.x, series_approximation.b.y);
+    let c = Complex(series_approximation.c.x, series_approximation.c.y);
+    let approximation = multiply_complex(
+      add_complex(multiply_complex(add_complex(multiply_complex(c, offset), b), offset), a),
+      offset
     );
-}
\ No newline at end of file
+
+    delta = normalise_scaled_complex(ScaledComplex(approximation, series_approximation.exponent));
+    if series_approximation.iterations == 0u {
+      // `dz` starts at zero. Give it the exponent of `dc`, so that `dc` doesn't underflow when
+      // it's added.
+      delta.exponent = scale.exponent;
+    }
+    reference_iteration = series_approximation.iterations;
+    iteration_count = series_approximation.iterations;
+  }
+
+  let unscaled_delta = unscale_complex(delta);
+  let value = add_complex(reference_orbit[reference_iteration], unscaled_delta);
+
+  // Rebase when the pixel is closer to zero than to the reference orbit, or when the reference
+  // orbit has no more values. `reference_orbit[0]` is zero, so `dz = z` at the start of the orbit.
+  if
+    reference_iteration + 1u >= arrayLength(&reference_orbit) ||
+    length_complex(value) < length_complex(unscaled_delta)
+  {
+    delta = normalise_scaled_complex(ScaledComplex(value, 0));
+    reference_iteration = 0u;
+  }
+
+  let reference_value = reference_orbit[reference_iteration];
+  let next_delta = normalise_scaled_complex(ScaledComplex(
+    add_complex(
+      add_complex(
+        scale_complex(2.0, multiply_complex(reference_value, delta.value)),
+        scale_complex(exp2(f32(delta.exponent)), multiply_complex(delta.value, delta.value))
+      ),
+      scale_complex(exp2(f32(scale.exponent - delta.exponent)), delta_c)
+    ),
+    delta.exponent
+  ));
+  let next_reference_iteration = reference_iteration + 1u;
+
+  output[index].escaped =
+    u32(length_complex(value) >= escape_threshold(FORMULA_MULTIBROT, 2.0));
+  output[index].iteration_count = iteration_count + 1u;
+  output[index].previous_value = value;
+  output[index].current_value =
+    add_complex(reference_orbit[next_reference_iteration], unscale_complex(next_delta));
+  output[index].engine_state =
+    store_perturbation_state(PerturbationState(next_delta, next_reference_iteration));
+}
+
+/*
+Apply one iteration of `formula` to `value` in double-single precision.
+
+Only supports the escape-time formulas with integer exponents, which only need addition and
+multiplication. See `Engine::supports` in `compute.rs`.
+*/
+fn iterate_double_single(formula: u32, exponent: f32, value: DoubleSingleComplex, c: DoubleSingleComplex) -> DoubleSingleComplex {
+  var folded = value;
+  switch formula {
+    case 1u, 5u: { // FORMULA_BURNING_SHIP, FORMULA_CELTIC_BURNING_SHIP
+      folded = DoubleSingleComplex(abs_double_single(value.real), abs_double_single(value.imaginary));
+    }
+    case 2u, 4u: { // FORMULA_TRICORN, FORMULA_CELTIC_MANDELBAR
+      folded = DoubleSingleComplex(value.real, negate_double_single(value.imaginary));
+    }
+    default: {}
+  }
+
+  var power = folded;
+  for (var i = 1u; i < u32(exponent); i++) {
+    power = multiply_double_single_complex(power, folded);
+  }
+
+  switch formula {
+    case 3u, 4u, 5u: { // FORMULA_CELTIC, FORMULA_CELTIC_MANDELBAR, FORMULA_CELTIC_BURNING_SHIP
+      power = DoubleSingleComplex(abs_double_single(power.real), power.imaginary);
+    }
+    default: {}
+  }
+
+  return add_double_single_complex(power, c);
+}
+
+/*
+Iterate the escape-time formulas using `DoubleSingleComplex`s, for zooms up to about `1e12`.
+
+The high parts of the pixel's value are stored in `current_value`, and the low parts in
+`engine_state.xy`.
+*/
+@compute @workgroup_size(1, 64, 1)
+fn double_single(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+  let index = global_invocation_id.x * (MANDELBROT_DISPATCH_SIZE_Y * MANDELBROT_WORKGROUP_SIZE_Y) + global_invocation_id.y;
+
+  let pixel = input[index];
+  output[index].x = pixel.x;
+  output[index].y = pixel.y;
+
+  // `origin + scale * (2 * (x, y) / screen_size - 1)`, where `2 * (x, y) - screen_size` is exact.
+  let c = add_double_single_complex(
+    DoubleSingleComplex(DoubleSingle(origin.x, origin.z), DoubleSingle(origin.y, origin.w)),
+    DoubleSingleComplex(
+      multiply_double_single(
+        double_single_scale,
+        divide_f32_double_single(f32(2u * pixel.x) - f32(screen_size.x), f32(screen_size.x))
+      ),
+      multiply_double_single(
+        double_single_scale,
+        divide_f32_double_single(f32(2u * pixel.y) - f32(screen_size.y), f32(screen_size.y))
+      )
+    )
+  );
+
+  var value = DoubleSingleComplex(
+    DoubleSingle(pixel.current_value.real, bitcast<f32>(pixel.engine_state.x)),
+    DoubleSingle(pixel.current_value.imaginary, bitcast<f32>(pixel.engine_state.y))
+  );
+  if pixel.iteration_count == 0u {
+    value = DoubleSingleComplex(DoubleSingle(0.0, 0.0), DoubleSingle(0.0, 0.0));
+  }
+
+  let next_value = iterate_double_single(formula, exponent, value, c);
+
+  output[index].escaped =
+    u32(length_complex(high_complex(value)) >= escape_threshold(formula, exponent));
+  output[index].iteration_count = pixel.iteration_count + 1u;
+  output[index].previous_value = high_complex(value);
+  output[index].current_value = high_complex(next_value);
+  output[index].engine_state = vec4<u32>(
+    bitcast<u32>(next_value.real.low),
+    bitcast<u32>(next_value.imaginary.low),
+    0u,
+    0u
+  );
+}
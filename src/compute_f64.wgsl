@@ -0,0 +1,118 @@
+/*
+`compute.wgsl#double_single` in native double precision, for adapters with
+`wgpu::Features::SHADER_FLOAT64`.
+
+This is a separate module because `compute.wgsl` must compile without `f64`. It uses the same
+bindings as `compute.wgsl`, and the same `Pixel` layout.
+*/
+
+struct Complex{real: f32, imaginary: f32}
+
+struct Complex64{real: f64, imaginary: f64}
+
+fn add_complex64(first: Complex64, second: Complex64) -> Complex64 {
+  return Complex64(first.real + second.real, first.imaginary + second.imaginary);
+}
+
+fn multiply_complex64(first: Complex64, second: Complex64) -> Complex64 {
+  let a = first.real * second.real;
+  let b = first.real * second.imaginary + first.imaginary * second.real;
+  let c = first.imaginary * second.imaginary;
+
+  return Complex64(a - c, b);
+}
+
+// Round to a `Complex`.
+fn to_complex(value: Complex64) -> Complex {
+  return Complex(f32(value.real), f32(value.imaginary));
+}
+
+// Corresponds to `compute.wgsl#Pixel`.
+struct Pixel{
+  x : u32,
+  y : u32,
+  escaped : u32,
+  current_value : Complex,
+  iteration_count : u32,
+  previous_value : Complex,
+  // `compute.wgsl#Pixel.engine_state`, which holds the pixel's value.
+  value : vec2<f64>,
+}
+
+// Corresponds to `compute.wgsl#DoubleSingle`.
+struct DoubleSingle{high: f32, low: f32}
+
+@group(0) @binding(0) var<uniform> screen_size : vec2<u32>;
+@group(0) @binding(2) var<uniform> origin : vec4<f32>;
+@group(0) @binding(3) var<uniform> exponent : f32;
+@group(0) @binding(4) var<uniform> formula : u32;
+@group(0) @binding(10) var<uniform> double_single_scale : DoubleSingle;
+
+@group(1) @binding(0) var<storage, read> input : array<Pixel>;
+@group(1) @binding(1) var<storage, read_write> output : array<Pixel>;
+
+// Corresponds to `compute.wgsl#escape_threshold`, for the formulas supported here.
+fn escape_threshold(exponent: f32) -> f32 {
+  return max(2.0, pow(2.0, 1.0 / (exponent - 1.0)));
+}
+
+// Corresponds to `compute.wgsl#iterate_double_single`.
+fn iterate(formula: u32, exponent: f32, value: Complex64, c: Complex64) -> Complex64 {
+  var folded = value;
+  switch formula {
+    case 1u, 5u: { // FORMULA_BURNING_SHIP, FORMULA_CELTIC_BURNING_SHIP
+      folded = Complex64(abs(value.real), abs(value.imaginary));
+    }
+    case 2u, 4u: { // FORMULA_TRICORN, FORMULA_CELTIC_MANDELBAR
+      folded = Complex64(value.real, -value.imaginary);
+    }
+    default: {}
+  }
+
+  var power = folded;
+  for (var i = 1u; i < u32(exponent); i++) {
+    power = multiply_complex64(power, folded);
+  }
+
+  switch formula {
+    case 3u, 4u, 5u: { // FORMULA_CELTIC, FORMULA_CELTIC_MANDELBAR, FORMULA_CELTIC_BURNING_SHIP
+      power = Complex64(abs(power.real), power.imaginary);
+    }
+    default: {}
+  }
+
+  return add_complex64(power, c);
+}
+
+let MANDELBROT_DISPATCH_SIZE_Y = 1024u;
+let MANDELBROT_WORKGROUP_SIZE_Y = 64u;
+
+@compute @workgroup_size(1, 64, 1)
+fn mandelbrot_f64(@builtin(global_invocation_id) global_invocation_id: vec3<u32>) {
+  let index = global_invocation_id.x * (MANDELBROT_DISPATCH_SIZE_Y * MANDELBROT_WORKGROUP_SIZE_Y) + global_invocation_id.y;
+
+  let pixel = input[index];
+  output[index].x = pixel.x;
+  output[index].y = pixel.y;
+
+  let scale = f64(double_single_scale.high) + f64(double_single_scale.low);
+  let c = Complex64(
+    f64(origin.x) + f64(origin.z) + scale * (f64(2u * pixel.x) / f64(screen_size.x) - f64(1.0)),
+    f64(origin.y) + f64(origin.w) + scale * (f64(2u * pixel.y) / f64(screen_size.y) - f64(1.0))
+  );
+
+  var value = Complex64(pixel.value.x, pixel.value.y);
+  if pixel.iteration_count == 0u {
+    value = Complex64(f64(0.0), f64(0.0));
+  }
+
+  let next_value = iterate(formula, exponent, value, c);
+
+  let value_f32 = to_complex(value);
+  output[index].escaped =
+    u32(sqrt(value_f32.real * value_f32.real + value_f32.imaginary * value_f32.imaginary) >= escape_threshold(exponent));
+  output[index].iteration_count = pixel.iteration_count + 1u;
+  output[index].previous_value = value_f32;
+  output[index].current_value = to_complex(next_value);
+  output[index].value = vec2<f64>(next_value.real, next_value.imaginary);
+}
@@ -0,0 +1,363 @@
+/*!
+User-supplied formulas.
+
+A custom formula is a WGSL file that defines a step function:
+
+```wgsl
+fn step(z: Complex, c: Complex) -> Complex {
+  return add_complex(multiply_complex(z, z), c);
+}
+```
+
+It's spliced into `compute.wgsl` in place of `custom_step`, and used for
+[`Formula::Custom`](crate::formula::Formula::Custom). The file can also declare its own helper
+functions and constants.
+
+The spliced shader is validated with [`naga`] before it's handed to `wgpu`, which would
+otherwise panic on an invalid shader. Errors are reported against the user's file where
+possible, and against `compute.wgsl` otherwise.
+*/
+
+use std::{
+    fmt::Display,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use crate::compute;
+
+const BEGIN_MARKER: &str = "// BEGIN CUSTOM FORMULA\n";
+const END_MARKER: &str = "// END CUSTOM FORMULA\n";
+
+/// The name that `step` is renamed to in the spliced shader.
+const STEP_NAME: &str = "custom_step";
+
+pub struct CustomFormula {
+    path: PathBuf,
+    shader_source: String,
+}
+
+impl CustomFormula {
+    /// Load a custom formula from `path`, and splice it into `compute.wgsl`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+
+        let snippet = std::fs::read_to_string(&path).map_err(|err| Error::Io {
+            path: path.clone(),
+            err,
+        })?;
+
+        let step_offset = find_step_declaration(&snippet)
+            .ok_or_else(|| Error::MissingStep { path: path.clone() })?;
+
+        let splice = Splice::new(&snippet, step_offset);
+
+        match validate(&splice.shader_source) {
+            Ok(()) => Ok(CustomFormula {
+                path,
+                shader_source: splice.shader_source,
+            }),
+            Err((message, labels)) => Err(Error::Shader {
+                report: splice.report(&path, &snippet, &message, &labels),
+                path,
+            }),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `compute.wgsl`, with `custom_step` replaced by the custom formula.
+    pub fn shader_source(&self) -> &str {
+        &self.shader_source
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        err: std::io::Error,
+    },
+    MissingStep {
+        path: PathBuf,
+    },
+    /// `report` describes the problem in terms of the custom formula's source code.
+    Shader {
+        path: PathBuf,
+        report: String,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io { path, err } => write!(f, "failed to read {}: {}", path.display(), err),
+            Error::MissingStep { path } => write!(
+                f,
+                "{} must declare `fn step(z: Complex, c: Complex) -> Complex`",
+                path.display()
+            ),
+            Error::Shader { path, report } => {
+                write!(f, "invalid formula in {}:\n{}", path.display(), report)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Find the byte offset of `step` in the snippet's `fn step(` declaration.
+fn find_step_declaration(snippet: &str) -> Option<usize> {
+    let is_identifier = |char: char| char.is_alphanumeric() || char == '_';
+
+    snippet
+        .match_indices("step")
+        .map(|(offset, _)| offset)
+        .find(|offset| {
+            let before = &snippet[..*offset];
+            let after = &snippet[offset + "step".len()..];
+
+            let before_trimmed = before.trim_end();
+            let follows_fn = before_trimmed.len() < before.len()
+                && before_trimmed
+                    .strip_suffix("fn")
+                    .is_some_and(|rest| !rest.ends_with(is_identifier));
+
+            follows_fn && !after.starts_with(is_identifier) && after.trim_start().starts_with('(')
+        })
+}
+
+/**
+`compute.wgsl` with a custom formula in place of `custom_step`.
+
+Keeps enough information to map offsets in the spliced shader back to the custom formula or
+to `compute.wgsl`.
+*/
+struct Splice {
+    shader_source: String,
+    /// The offset in `compute.wgsl` where the custom formula region starts.
+    region_start: usize,
+    /// The offset in `compute.wgsl` where the custom formula region ends.
+    region_end: usize,
+    /// The offset of `step` in the custom formula.
+    step_offset: usize,
+    /// The length of the renamed custom formula.
+    renamed_length: usize,
+}
+
+/// A location in one of the files that make up a [`Splice`].
+enum Origin {
+    Template(usize),
+    Snippet(usize),
+}
+
+impl Splice {
+    fn new(snippet: &str, step_offset: usize) -> Self {
+        let template = compute::SHADER_SOURCE;
+        let region_start = template.find(BEGIN_MARKER).unwrap();
+        let region_end = template.find(END_MARKER).unwrap() + END_MARKER.len();
+
+        let mut renamed = String::with_capacity(snippet.len() + STEP_NAME.len());
+        renamed.push_str(&snippet[..step_offset]);
+        renamed.push_str(STEP_NAME);
+        renamed.push_str(&snippet[step_offset + "step".len()..]);
+        if !renamed.ends_with('\n') {
+            renamed.push('\n');
+        }
+
+        let shader_source = [&template[..region_start], &renamed, &template[region_end..]].concat();
+
+        Splice {
+            shader_source,
+            region_start,
+            region_end,
+            step_offset,
+            renamed_length: renamed.len(),
+        }
+    }
+
+    fn origin(&self, offset: usize) -> Origin {
+        if offset < self.region_start {
+            Origin::Template(offset)
+        } else if offset < self.region_start + self.renamed_length {
+            let offset = offset - self.region_start;
+            let rename_end = self.step_offset + STEP_NAME.len();
+            if offset < self.step_offset {
+                Origin::Snippet(offset)
+            } else if offset < rename_end {
+                // Somewhere inside `custom_step`; point at the start of `step`.
+                Origin::Snippet(self.step_offset)
+            } else {
+                Origin::Snippet(offset - (STEP_NAME.len() - "step".len()))
+            }
+        } else {
+            Origin::Template(offset - self.region_start - self.renamed_length + self.region_end)
+        }
+    }
+
+    fn report(
+        &self,
+        path: &Path,
+        snippet: &str,
+        message: &str,
+        labels: &[(Range<usize>, String)],
+    ) -> String {
+        let mut report = format!("error: {}\n", message.replace(STEP_NAME, "step"));
+        for (range, label) in labels {
+            let label = label.replace(STEP_NAME, "step");
+            let length = range.end.saturating_sub(range.start).max(1);
+            let excerpt = match self.origin(range.start) {
+                Origin::Snippet(offset) => {
+                    excerpt(&path.display().to_string(), snippet, offset, length, &label)
+                }
+                Origin::Template(offset) => excerpt(
+                    "compute.wgsl",
+                    compute::SHADER_SOURCE,
+                    offset,
+                    length,
+                    &label,
+                ),
+            };
+            report.push_str(&excerpt);
+        }
+        report
+    }
+}
+
+/// Describe `length` bytes at `offset` in `source`, in the style of `rustc`.
+fn excerpt(path: &str, source: &str, offset: usize, length: usize, label: &str) -> String {
+    let offset = offset.min(source.len());
+    let line_start = source[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = source[offset..]
+        .find('\n')
+        .map_or(source.len(), |index| offset + index);
+    let line = &source[line_start..line_end];
+    let line_number = source[..offset].matches('\n').count() + 1;
+    let column = source[line_start..offset].chars().count();
+    let underline = length.min(line_end - offset).max(1);
+
+    let gutter = " ".repeat(line_number.to_string().len());
+    format!(
+        "{gutter}--> {path}:{line_number}:{column_number}\n\
+         {gutter} |\n\
+         {line_number} | {line}\n\
+         {gutter} | {padding}{carets} {label}\n",
+        column_number = column + 1,
+        padding = " ".repeat(column),
+        carets = "^".repeat(underline),
+    )
+}
+
+type Labels = Vec<(Range<usize>, String)>;
+
+/// Parse and validate a shader, returning an error message and labelled source ranges.
+fn validate(source: &str) -> Result<(), (String, Labels)> {
+    let module = naga::front::wgsl::parse_str(source).map_err(|err| {
+        (
+            err.message().to_string(),
+            err.labels()
+                .map(|(range, label)| (range, label.to_string()))
+                .collect(),
+        )
+    })?;
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::empty(),
+    )
+    .validate(&module)
+    .map_err(|err| {
+        let mut message = err.as_inner().to_string();
+        let mut source: Option<&dyn std::error::Error> = std::error::Error::source(err.as_inner());
+        while let Some(err) = source {
+            message.push_str(&format!(": {}", err));
+            source = err.source();
+        }
+
+        (
+            message,
+            err.spans()
+                .filter_map(|(span, label)| span.to_range().map(|range| (range, label.clone())))
+                .collect(),
+        )
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Splice `snippet` into `compute.wgsl`, and report any error against `formula.wgsl`.
+    fn splice(snippet: &str) -> Result<String, String> {
+        let step_offset = find_step_declaration(snippet).expect("no `fn step(`");
+        let splice = Splice::new(snippet, step_offset);
+        match validate(&splice.shader_source) {
+            Ok(()) => Ok(splice.shader_source),
+            Err((message, labels)) => {
+                Err(splice.report(Path::new("formula.wgsl"), snippet, &message, &labels))
+            }
+        }
+    }
+
+    #[test]
+    fn snippets_replace_the_default_formula() {
+        let snippet = "fn cube(z: Complex) -> Complex {\n  \
+                         return power_complex(z, 3.0);\n\
+                       }\n\
+                       fn step(z: Complex, c: Complex) -> Complex {\n  \
+                         return add_complex(cube(z), c);\n\
+                       }";
+        let shader_source = splice(snippet).unwrap();
+
+        assert!(shader_source.contains(
+            "fn custom_step(z: Complex, c: Complex) -> Complex {\n  \
+               return add_complex(cube(z), c);\n}\n"
+        ));
+        assert!(shader_source.contains("fn cube(z: Complex) -> Complex {"));
+        assert!(!shader_source.contains("return add_complex(multiply_complex(z, z), c);"));
+        assert!(!shader_source.contains(BEGIN_MARKER));
+        assert!(!shader_source.contains(END_MARKER));
+    }
+
+    #[test]
+    fn step_declarations_are_found() {
+        assert_eq!(find_step_declaration("fn step(z: Complex"), Some(3));
+        assert_eq!(find_step_declaration("fn  step (z: Complex"), Some(4));
+        assert_eq!(
+            find_step_declaration("fn half_step(z: Complex) {}\nfn step(z: Complex"),
+            Some(31)
+        );
+
+        for snippet in [
+            "",
+            "fn steps(z: Complex",
+            "fn half_step(z: Complex",
+            "let step = 1.0;",
+            "fnstep(z: Complex",
+            "fn step",
+        ] {
+            assert_eq!(find_step_declaration(snippet), None, "{:?}", snippet);
+        }
+    }
+
+    #[test]
+    fn errors_are_reported_at_the_snippets_line() {
+        let snippet = "fn step(z: Complex, c: Complex) -> Complex {\n  \
+                         let w = multiply_complex(z, z);\n  \
+                         return add_complex(w, undefined_value);\n\
+                       }\n";
+        let report = splice(snippet).unwrap_err();
+
+        assert!(report.contains("--> formula.wgsl:3:"), "{}", report);
+        assert!(
+            report.contains("return add_complex(w, undefined_value);"),
+            "{}",
+            report
+        );
+        assert!(!report.contains(STEP_NAME), "{}", report);
+    }
+}
@@ -0,0 +1,177 @@
+/*!
+Double-single numbers, for [`Engine::DoubleSingle`](crate::compute::Engine::DoubleSingle).
+
+A double-single number is a pair of `f32`s whose sum has about twice the precision of an `f32`.
+They let us zoom to about `1e12` on any adapter, without the `SHADER_F64` feature.
+*/
+
+use bytemuck::{Pod, Zeroable};
+use dashu_float::FBig;
+
+use crate::{perturbation::BigComplex, pixel::Complex};
+
+/// `high + low`. Corresponds to `compute.wgsl#DoubleSingle`.
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq)]
+pub struct DoubleSingle {
+    pub high: f32,
+    pub low: f32,
+}
+
+/// Values too large for an `f32` become infinite, with a low part of zero.
+impl From<f64> for DoubleSingle {
+    fn from(value: f64) -> Self {
+        let high = value as f32;
+        if !high.is_finite() {
+            return DoubleSingle { high, low: 0.0 };
+        }
+
+        DoubleSingle {
+            high,
+            low: (value - high as f64) as f32,
+        }
+    }
+}
+
+/**
+Goes through an `f64`, which has more precision than a double-single number.
+
+`FBig` rounds towards zero, so splitting it directly would leave a low part as large as the
+high part's last bit, and lose a bit of precision when the low part is rounded.
+*/
+impl From<&FBig> for DoubleSingle {
+    fn from(value: &FBig) -> Self {
+        DoubleSingle::from(value.to_f64().value())
+    }
+}
+
+/**
+`high + low`, where each component is a [`DoubleSingle`].
+
+Laid out like `compute.wgsl#origin`, with both high parts first. The `f32` approximation of the
+number is [`DoubleSingleComplex::high`].
+*/
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq)]
+pub struct DoubleSingleComplex {
+    pub high: Complex,
+    pub low: Complex,
+}
+
+impl From<&BigComplex> for DoubleSingleComplex {
+    fn from(value: &BigComplex) -> Self {
+        let real = DoubleSingle::from(&value.real);
+        let imaginary = DoubleSingle::from(&value.imaginary);
+        DoubleSingleComplex {
+            high: Complex::new(real.high, imaginary.high),
+            low: Complex::new(real.low, imaginary.low),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The relative precision of a double-single number.
+    const EPSILON: f64 = 1.0 / (1_u64 << 48) as f64;
+
+    /// Finite values whose low part is still a normal `f32`.
+    const VALUES: [f64; 10] = [
+        0.0,
+        1.0,
+        -0.1,
+        std::f64::consts::PI,
+        -1.234_567_890_123_456_7e-12,
+        9.876_543_210_987_654e20,
+        // Just inside the range of normal `f32`s, with room for the low part.
+        f32::MIN_POSITIVE as f64 * (1 << 24) as f64 * 1.000_000_000_1,
+        -f32::MIN_POSITIVE as f64 * (1 << 24) as f64 * 1.000_000_000_1,
+        // Just below the largest `f32`, so that the high part doesn't round up to infinity.
+        f32::MAX as f64 * 0.999_999_999_9,
+        -f32::MAX as f64 * 0.999_999_999_9,
+    ];
+
+    fn assert_recovers(value: f64, double_single: DoubleSingle) {
+        let recovered = double_single.high as f64 + double_single.low as f64;
+        assert!(
+            (recovered - value).abs() <= value.abs() * EPSILON,
+            "{:?} from {:?}",
+            double_single,
+            value
+        );
+    }
+
+    #[test]
+    fn f64s_are_split_without_losing_precision() {
+        for value in VALUES {
+            assert_recovers(value, DoubleSingle::from(value));
+        }
+    }
+
+    #[test]
+    fn big_floats_are_split_without_losing_precision() {
+        for value in VALUES {
+            assert_recovers(value, DoubleSingle::from(&FBig::try_from(value).unwrap()));
+        }
+    }
+
+    #[test]
+    fn big_complex_numbers_are_split_without_losing_precision() {
+        for (real, imaginary) in VALUES.into_iter().zip(VALUES.into_iter().rev()) {
+            let value = DoubleSingleComplex::from(&BigComplex::from_f64(real, imaginary, 64));
+            assert_recovers(
+                real,
+                DoubleSingle {
+                    high: value.high.real,
+                    low: value.low.real,
+                },
+            );
+            assert_recovers(
+                imaginary,
+                DoubleSingle {
+                    high: value.high.imaginary,
+                    low: value.low.imaginary,
+                },
+            );
+        }
+    }
+
+    #[test]
+    fn values_beyond_the_range_of_f32_are_infinite() {
+        for value in [f32::MAX as f64 * 1.000_000_1, f64::MAX, f64::INFINITY] {
+            for (value, expected) in [(value, f32::INFINITY), (-value, f32::NEG_INFINITY)] {
+                let expected = DoubleSingle {
+                    high: expected,
+                    low: 0.0,
+                };
+                assert_eq!(DoubleSingle::from(value), expected, "{:?}", value);
+                if value.is_finite() {
+                    let big = FBig::try_from(value).unwrap();
+                    assert_eq!(DoubleSingle::from(&big), expected, "{:?}", value);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn values_below_the_range_of_f32_keep_their_absolute_precision() {
+        // The low part underflows first, then the high part.
+        let smallest = f32::from_bits(1) as f64;
+        for value in [
+            f32::MIN_POSITIVE as f64 * 1.000_000_000_1,
+            f32::MIN_POSITIVE as f64 * 0.999_999_999_9,
+            smallest * 0.4,
+            -f32::MIN_POSITIVE as f64 * 0.999_999_999_9,
+        ] {
+            let double_single = DoubleSingle::from(value);
+            let recovered = double_single.high as f64 + double_single.low as f64;
+            assert!(
+                (recovered - value).abs() <= smallest,
+                "{:?} from {:?}",
+                double_single,
+                value
+            );
+        }
+    }
+}
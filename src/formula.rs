@@ -0,0 +1,372 @@
+//! Fractal formulas.
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::pixel::{Complex, ParseComplexError};
+
+/**
+The iteration function used by `compute.wgsl#mandelbrot`.
+
+The escape-time formulas are raised to the exponent `n` from `compute.wgsl#exponent`, so
+[`Formula::Multibrot`] with `n = 2` is the Mandelbrot set. The Newton and magnet formulas
+ignore the exponent.
+
+The discriminants correspond to the `FORMULA_*` constants in `compute.wgsl`.
+*/
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u32)]
+pub enum Formula {
+    /// `z^n + c`
+    #[default]
+    Multibrot = 0,
+
+    /// `(|Re(z)| + i|Im(z)|)^n + c` ([Wikipedia](https://en.wikipedia.org/wiki/Burning_Ship_fractal))
+    BurningShip = 1,
+
+    /// `conj(z)^n + c`, also known as the Mandelbar set ([Wikipedia](https://en.wikipedia.org/wiki/Tricorn_(mathematics)))
+    Tricorn = 2,
+
+    /// `|Re(z^n)| + i Im(z^n) + c`
+    Celtic = 3,
+
+    /// [`Formula::Celtic`] applied to `conj(z)`.
+    CelticMandelbar = 4,
+
+    /// [`Formula::Celtic`] applied to `|Re(z)| + i|Im(z)|`.
+    CelticBurningShip = 5,
+
+    /**
+    [Newton's method](https://en.wikipedia.org/wiki/Newton_fractal) for a [`Polynomial`] `p`:
+    `z - R * p(z) / p'(z)`, starting from `z = c`.
+
+    `R` is the relaxation parameter from `compute.wgsl#relaxation`; `R = 1` is the
+    unrelaxed method. Pixels stop iterating when they converge, and are coloured by the
+    root they converged to.
+    */
+    Newton = 6,
+
+    /**
+    [`Formula::Newton`] with `c` added after each step: `z - R * p(z) / p'(z) + c`, starting
+    from `z = 1`.
+
+    Pixels stop iterating when they converge. The fixed point they converge to depends on
+    `c`, so they are coloured by iteration count alone.
+    */
+    Nova = 7,
+
+    /**
+    A Mandelbrot-style [Phoenix fractal](https://en.wikipedia.org/wiki/Phoenix_fractal):
+    `z^n + c + p * z'`, where `z'` is the value before `z` (see
+    [`Pixel::previous_value`](crate::pixel::Pixel::previous_value)) and `p` is
+    `compute.wgsl#phoenix_parameter`.
+    */
+    Phoenix = 8,
+
+    /**
+    The magnetism model of type I: `((z^2 + c - 1) / (2z + c - 2))^2`.
+
+    Pixels stop iterating when they escape, or when they converge to the fixed point `1`.
+    Pixels that converge are coloured by [`Formula::roots`].
+    */
+    MagnetOne = 9,
+
+    /// The magnetism model of type II, which is like [`Formula::MagnetOne`] with a cubic
+    /// rational function: `((z^3 + 3(c - 1)z + (c - 1)(c - 2)) / (3z^2 + 3(c - 2)z + (c - 1)(c - 2) + 1))^2`.
+    MagnetTwo = 10,
+
+    /// A user-supplied step function (see [`crate::custom_formula`]).
+    Custom = 11,
+}
+
+impl Formula {
+    pub const ALL: [Formula; 12] = [
+        Formula::Multibrot,
+        Formula::BurningShip,
+        Formula::Tricorn,
+        Formula::Celtic,
+        Formula::CelticMandelbar,
+        Formula::CelticBurningShip,
+        Formula::Newton,
+        Formula::Nova,
+        Formula::Phoenix,
+        Formula::MagnetOne,
+        Formula::MagnetTwo,
+        Formula::Custom,
+    ];
+
+    /// The next formula in [`Formula::ALL`], wrapping around at the end.
+    pub fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|formula| *formula == self)
+            .unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Formula::Multibrot => "multibrot",
+            Formula::BurningShip => "burning-ship",
+            Formula::Tricorn => "tricorn",
+            Formula::Celtic => "celtic",
+            Formula::CelticMandelbar => "celtic-mandelbar",
+            Formula::CelticBurningShip => "celtic-burning-ship",
+            Formula::Newton => "newton",
+            Formula::Nova => "nova",
+            Formula::Phoenix => "phoenix",
+            Formula::MagnetOne => "magnet-1",
+            Formula::MagnetTwo => "magnet-2",
+            Formula::Custom => "custom",
+        }
+    }
+
+    /**
+    The points that pixels are coloured by converging to, for
+    [`HistogramColouring::set_roots`](crate::colour::HistogramColouring::set_roots).
+
+    These are the roots of `polynomial` for [`Formula::Newton`], and the fixed point `1` for
+    the magnet formulas.
+    */
+    pub fn roots(self, polynomial: &Polynomial) -> Vec<Complex> {
+        match self {
+            Formula::Newton => polynomial.roots(),
+            Formula::MagnetOne | Formula::MagnetTwo => vec![Complex::ONE],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/**
+A polynomial with complex coefficients, for [`Formula::Newton`] and [`Formula::Nova`].
+
+Coefficients are stored from the lowest degree to the highest, which is the layout
+expected by `compute.wgsl#coefficients`. The leading coefficient is never zero.
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polynomial {
+    coefficients: Vec<Complex>,
+}
+
+impl Polynomial {
+    /// Returns `None` for constant polynomials, which have no roots to converge to.
+    pub fn new(mut coefficients: Vec<Complex>) -> Option<Self> {
+        while coefficients.last() == Some(&Complex::ZERO) {
+            coefficients.pop();
+        }
+
+        if coefficients.len() < 2 {
+            None
+        } else {
+            Some(Polynomial { coefficients })
+        }
+    }
+
+    pub fn coefficients(&self) -> &[Complex] {
+        &self.coefficients
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+
+    /// Evaluate the polynomial at `value` using [Horner's method](https://en.wikipedia.org/wiki/Horner%27s_method).
+    pub fn evaluate(&self, value: Complex) -> Complex {
+        self.coefficients
+            .iter()
+            .rev()
+            .fold(Complex::ZERO, |acc, coefficient| acc * value + *coefficient)
+    }
+
+    /**
+    Approximate the polynomial's roots using the
+    [Durand-Kerner method](https://en.wikipedia.org/wiki/Durand%E2%80%93Kerner_method).
+
+    Repeated roots are returned once per multiplicity.
+    */
+    pub fn roots(&self) -> Vec<Complex> {
+        const ITERATIONS: usize = 500;
+
+        let leading = *self.coefficients.last().unwrap();
+
+        // The method's starting points must not be real, and must not be roots of unity.
+        let seed = Complex::new(0.4, 0.9);
+        let mut roots: Vec<Complex> =
+            std::iter::successors(Some(Complex::ONE), |root| Some(*root * seed))
+                .take(self.degree())
+                .collect();
+
+        for _ in 0..ITERATIONS {
+            for index in 0..roots.len() {
+                let root = roots[index];
+                let denominator = roots
+                    .iter()
+                    .enumerate()
+                    .filter(|(other_index, _)| *other_index != index)
+                    .fold(leading, |acc, (_, other)| acc * (root - *other));
+                roots[index] = root - self.evaluate(root) / denominator;
+            }
+        }
+
+        roots
+    }
+}
+
+/// `z^3 - 1`, the classic Newton fractal.
+impl Default for Polynomial {
+    fn default() -> Self {
+        Polynomial::new(vec![
+            Complex::new(-1.0, 0.0),
+            Complex::ZERO,
+            Complex::ZERO,
+            Complex::ONE,
+        ])
+        .unwrap()
+    }
+}
+
+/// Formats as the coefficients from the highest degree to the lowest, separated by spaces.
+impl Display for Polynomial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, coefficient) in self.coefficients.iter().rev().enumerate() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", coefficient)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseFormulaError(String);
+
+impl Display for ParseFormulaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = Formula::ALL.map(Formula::name);
+        write!(
+            f,
+            "unknown formula: {:?} (expected one of {})",
+            self.0,
+            names.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseFormulaError {}
+
+/// Parses a formula's [`Formula::name`].
+impl FromStr for Formula {
+    type Err = ParseFormulaError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Formula::ALL
+            .into_iter()
+            .find(|formula| formula.name() == input)
+            .ok_or_else(|| ParseFormulaError(input.to_string()))
+    }
+}
+
+#[derive(Debug)]
+pub enum ParsePolynomialError {
+    Coefficient(ParseComplexError),
+    Constant,
+}
+
+impl Display for ParsePolynomialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParsePolynomialError::Coefficient(error) => error.fmt(f),
+            ParsePolynomialError::Constant => write!(f, "polynomial must not be constant"),
+        }
+    }
+}
+
+impl std::error::Error for ParsePolynomialError {}
+
+/**
+Parses whitespace-separated coefficients from the highest degree to the lowest, using
+the syntax from [`Complex::from_str`].
+
+For example, `z^3 - 1` is `1 0 0 -1` and `z^2 + i` is `1 0 i`.
+*/
+impl FromStr for Polynomial {
+    type Err = ParsePolynomialError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut coefficients = input
+            .split_whitespace()
+            .map(Complex::from_str)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ParsePolynomialError::Coefficient)?;
+        coefficients.reverse();
+        Polynomial::new(coefficients).ok_or(ParsePolynomialError::Constant)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polynomials_are_parsed() {
+        let polynomial: Polynomial = "1 0 0 -1".parse().unwrap();
+        assert_eq!(polynomial, Polynomial::default());
+        assert_eq!(polynomial.to_string(), "1 0 0 -1");
+
+        // Leading zeros are dropped.
+        let polynomial: Polynomial = "0 2i 1-i".parse().unwrap();
+        assert_eq!(polynomial.degree(), 1);
+        assert_eq!(
+            polynomial.coefficients(),
+            [Complex::new(1.0, -1.0), Complex::new(0.0, 2.0)]
+        );
+        assert_eq!(
+            polynomial.to_string().parse::<Polynomial>().unwrap(),
+            polynomial
+        );
+    }
+
+    #[test]
+    fn invalid_polynomials_are_rejected() {
+        for input in ["", "5", "0 0 5", "0 0"] {
+            assert!(
+                matches!(
+                    input.parse::<Polynomial>(),
+                    Err(ParsePolynomialError::Constant)
+                ),
+                "{:?}",
+                input
+            );
+        }
+        for input in ["1 x", "1,0,-1", "1 0 -1j"] {
+            assert!(
+                matches!(
+                    input.parse::<Polynomial>(),
+                    Err(ParsePolynomialError::Coefficient(_))
+                ),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn roots_of_z_cubed_minus_one_are_the_cube_roots_of_unity() {
+        let half_root_three = 3.0_f32.sqrt() / 2.0;
+        let mut expected = vec![
+            Complex::ONE,
+            Complex::new(-0.5, half_root_three),
+            Complex::new(-0.5, -half_root_three),
+        ];
+
+        let roots = Polynomial::default().roots();
+        assert_eq!(roots.len(), 3);
+        for root in roots {
+            let index = expected
+                .iter()
+                .position(|expected| (root - *expected).length() < 1e-5)
+                .unwrap_or_else(|| panic!("unexpected root {}", root));
+            expected.remove(index);
+        }
+    }
+}
@@ -0,0 +1,531 @@
+/*!
+The pixels being computed, and their colours.
+
+Each frame iterates the pixels that haven't escaped once more on the GPU, and colours the ones
+that escape. The window computes a frame every time it's redrawn, and batch mode computes them
+until the image is finished.
+*/
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use log::{debug, trace};
+use wgpu_mandelbrot::{
+    colour::{ColourRange, HistogramColouring},
+    command_buffer,
+    command_encoder::CommandEncoderExt,
+    compute::{self, Engine},
+    perturbation::{ReferenceOrbit, SeriesApproximation},
+    pixel::{Complex, Pixel},
+    screen, typed_buffer,
+    view::View,
+};
+
+/// The state of every pixel on a screen, on the CPU and the GPU.
+pub struct Frame {
+    size: screen::Size,
+    pub all_pixels: Vec<Pixel>,
+    pub unescaped_pixels: Vec<Pixel>,
+    newly_escaped_pixels: Vec<Pixel>,
+    /// Unescaped pixels that have reached the iteration limit.
+    pub limited_pixels: Vec<Pixel>,
+    pub colour_ranges: Vec<ColourRange>,
+    colour_ranges_buffer: typed_buffer::Buffer<ColourRange>,
+    pub histogram_colouring: HistogramColouring,
+    pixels_buffers: typed_buffer::DoubleBuffer<Pixel>,
+    pixels_staging_buffer: typed_buffer::Buffer<Pixel>,
+    reference_orbit: ReferenceOrbit,
+    reference_orbit_buffer: typed_buffer::Buffer<Complex>,
+    /// How many iterations the pixels that are left have been through, including the ones that
+    /// the series approximation skipped.
+    iterations: usize,
+}
+
+fn create_pixels(size: screen::Size) -> Vec<Pixel> {
+    (0..size.height)
+        .flat_map(move |y| {
+            (0..size.width).map(move |x| Pixel {
+                x,
+                y,
+                current_value: Complex::ZERO,
+                escaped: 0,
+                iteration_count: 0,
+                previous_value: Complex::ZERO,
+                engine_state: [0; 4],
+            })
+        })
+        .collect::<Vec<_>>()
+}
+
+fn create_pixels_buffers(
+    device: &wgpu::Device,
+    size: screen::Size,
+) -> typed_buffer::DoubleBuffer<Pixel> {
+    let pixels = create_pixels(size);
+
+    typed_buffer::DoubleBuffer {
+        input: typed_buffer::Builder::from(pixels.as_slice())
+            .with_label("pixels_buffer_1")
+            .with_usage(wgpu::BufferUsages::STORAGE)
+            .with_usage(wgpu::BufferUsages::COPY_SRC)
+            .create(device),
+
+        output: typed_buffer::Builder::from(pixels.as_slice())
+            .with_label("pixels_buffer_2")
+            .with_usage(wgpu::BufferUsages::STORAGE)
+            .with_usage(wgpu::BufferUsages::COPY_SRC)
+            .create(device),
+    }
+}
+
+fn create_pixels_staging_buffer(
+    device: &wgpu::Device,
+    size: screen::Size,
+) -> typed_buffer::Buffer<Pixel> {
+    typed_buffer::Builder::new(size.width as u64 * size.height as u64)
+        .with_label("pixels_staging_buffer")
+        .with_usage(wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ)
+        .create(device)
+}
+
+fn create_colour_ranges(size: screen::Size) -> Vec<ColourRange> {
+    std::iter::repeat_n(ColourRange::default(), (size.width * size.height) as usize).collect()
+}
+
+fn create_colour_ranges_buffer(
+    device: &wgpu::Device,
+    size: screen::Size,
+) -> typed_buffer::Buffer<ColourRange> {
+    typed_buffer::Builder::from(create_colour_ranges(size).as_slice())
+        .with_usage(wgpu::BufferUsages::STORAGE)
+        .create(device)
+}
+
+fn create_reference_orbit_buffer(
+    device: &wgpu::Device,
+    reference_orbit: &ReferenceOrbit,
+) -> typed_buffer::Buffer<Complex> {
+    typed_buffer::Builder::from(reference_orbit.values())
+        .with_label("reference-orbit-buffer")
+        .with_usage(wgpu::BufferUsages::STORAGE)
+        .create(device)
+}
+
+/**
+Move the pixels of a screen of `size` by `(x, y)`, after the view has been panned by the same
+amount.
+
+Escaped pixels still on the screen are kept, as are unescaped pixels if `keep_unescaped`. The
+rest start again from the first iteration.
+*/
+fn pan_pixels(
+    size: screen::Size,
+    x: i64,
+    y: i64,
+    keep_unescaped: bool,
+    all_pixels: &mut Vec<Pixel>,
+    unescaped_pixels: &mut Vec<Pixel>,
+) {
+    let mut pixels = create_pixels(size);
+
+    let escaped_pixels = all_pixels.iter().filter(|pixel| pixel.escaped == 1);
+    let kept_unescaped_pixels = unescaped_pixels.iter().filter(|_| keep_unescaped);
+    for pixel in escaped_pixels.chain(kept_unescaped_pixels) {
+        let new_x = pixel.x as i64 + x;
+        let new_y = pixel.y as i64 + y;
+        if (0..size.width as i64).contains(&new_x) && (0..size.height as i64).contains(&new_y) {
+            pixels[new_y as usize * size.width as usize + new_x as usize] = Pixel {
+                x: new_x as u32,
+                y: new_y as u32,
+                ..*pixel
+            };
+        }
+    }
+
+    *unescaped_pixels = pixels
+        .iter()
+        .filter(|pixel| pixel.escaped == 0)
+        .copied()
+        .collect();
+    *all_pixels = pixels;
+}
+
+/// Move the pixels in `unescaped_pixels` that have reached `iteration_limit` to `limited_pixels`.
+fn split_limited_pixels(
+    iteration_limit: u32,
+    unescaped_pixels: &mut Vec<Pixel>,
+    limited_pixels: &mut Vec<Pixel>,
+) {
+    limited_pixels.extend(
+        unescaped_pixels
+            .iter()
+            .filter(|pixel| pixel.iteration_count >= iteration_limit),
+    );
+    unescaped_pixels.retain(|pixel| pixel.iteration_count < iteration_limit);
+}
+
+impl Frame {
+    /// Start every pixel of a screen of `size` from the first iteration, with the reference
+    /// orbit at the center of `view`.
+    pub fn new(device: &wgpu::Device, size: screen::Size, view: &View) -> Self {
+        let reference_orbit = ReferenceOrbit::new(view.center());
+
+        Frame {
+            size,
+            all_pixels: create_pixels(size),
+            unescaped_pixels: create_pixels(size),
+            newly_escaped_pixels: Vec::new(),
+            limited_pixels: Vec::new(),
+            colour_ranges: create_colour_ranges(size),
+            colour_ranges_buffer: create_colour_ranges_buffer(device, size),
+            histogram_colouring: HistogramColouring::new(),
+            pixels_buffers: create_pixels_buffers(device, size),
+            pixels_staging_buffer: create_pixels_staging_buffer(device, size),
+            reference_orbit_buffer: create_reference_orbit_buffer(device, &reference_orbit),
+            reference_orbit,
+            iterations: 0,
+        }
+    }
+
+    /// Start every pixel of a screen of the new `size` from the first iteration, uncoloured.
+    pub fn resize(&mut self, device: &wgpu::Device, size: screen::Size) {
+        self.size = size;
+
+        self.colour_ranges = create_colour_ranges(size);
+        self.histogram_colouring.reset();
+
+        self.pixels_staging_buffer = create_pixels_staging_buffer(device, size);
+        std::mem::replace(
+            &mut self.pixels_buffers,
+            create_pixels_buffers(device, size),
+        )
+        .destroy();
+        self.all_pixels = create_pixels(size);
+        self.unescaped_pixels = create_pixels(size);
+
+        std::mem::replace(
+            &mut self.colour_ranges_buffer,
+            create_colour_ranges_buffer(device, size),
+        )
+        .destroy();
+    }
+
+    /// Move the reference orbit to the center of `view`, returning how many iterations the
+    /// pixels can skip with `engine`.
+    pub fn move_reference_orbit(
+        &mut self,
+        device: &wgpu::Device,
+        view: &View,
+        engine: Engine,
+    ) -> SeriesApproximation {
+        self.reference_orbit = ReferenceOrbit::new(view.center());
+        let series_approximation = if engine == Engine::Perturbation {
+            SeriesApproximation::new(&mut self.reference_orbit, view.scale())
+        } else {
+            SeriesApproximation::NONE
+        };
+        debug!(
+            "series approximation skips {} iterations",
+            series_approximation.iterations
+        );
+        self.iterations = series_approximation.iterations as usize;
+        std::mem::replace(
+            &mut self.reference_orbit_buffer,
+            create_reference_orbit_buffer(device, &self.reference_orbit),
+        )
+        .destroy();
+
+        series_approximation
+    }
+
+    /// Start every pixel from the first iteration again. The colours are left as they are.
+    pub fn reset(&mut self, queue: &wgpu::Queue) {
+        self.histogram_colouring.reset();
+
+        let pixels = create_pixels(self.size);
+        self.pixels_buffers.input.write(queue, &pixels);
+        self.pixels_buffers.output.write(queue, &pixels);
+        self.all_pixels = pixels.clone();
+        self.unescaped_pixels = pixels;
+        self.limited_pixels.clear();
+    }
+
+    /**
+    Move the pixels by `(x, y)`, after the view has been panned by the same amount, and colour
+    the escaped pixels that are left again.
+
+    Unescaped pixels are only kept if `engine` doesn't compute them relative to the reference
+    orbit, which has moved.
+    */
+    pub fn pan(&mut self, x: i64, y: i64, engine: Engine, iteration_limit: u32) {
+        debug!("panning pixels by {:?}", (x, y));
+        // Unescaped pixels' perturbations are relative to the old reference orbit.
+        self.unescaped_pixels.append(&mut self.limited_pixels);
+        pan_pixels(
+            self.size,
+            x,
+            y,
+            engine != Engine::Perturbation,
+            &mut self.all_pixels,
+            &mut self.unescaped_pixels,
+        );
+        self.set_iteration_limit(iteration_limit);
+
+        // Recolour the escaped pixels that are left.
+        self.clear_colours();
+        self.histogram_colouring.reset();
+        self.newly_escaped_pixels.clear();
+        self.newly_escaped_pixels
+            .extend(self.all_pixels.iter().filter(|pixel| pixel.escaped == 1));
+        self.histogram_colouring.update_colours(
+            self.size,
+            &self.all_pixels,
+            &self.newly_escaped_pixels,
+            &mut self.colour_ranges,
+        );
+    }
+
+    /// Carry on iterating the pixels that reached the old iteration limit, up to
+    /// `iteration_limit`.
+    pub fn set_iteration_limit(&mut self, iteration_limit: u32) {
+        self.unescaped_pixels.append(&mut self.limited_pixels);
+        split_limited_pixels(
+            iteration_limit,
+            &mut self.unescaped_pixels,
+            &mut self.limited_pixels,
+        );
+    }
+
+    /// Uncolour every pixel.
+    pub fn clear_colours(&mut self) {
+        self.colour_ranges.clear();
+        self.colour_ranges.extend(create_colour_ranges(self.size));
+    }
+
+    /// Uncolour the pixels that haven't escaped, once what's left of a
+    /// [`Preview`](wgpu_mandelbrot::history::Preview) is out of date.
+    pub fn clear_preview(&mut self) {
+        for (pixel, colour_range) in self.all_pixels.iter().zip(&mut self.colour_ranges) {
+            if pixel.escaped == 0 {
+                *colour_range = ColourRange::default();
+            }
+        }
+    }
+
+    /**
+    Iterate the pixels that haven't escaped or reached `iteration_limit` once more with
+    `compute_pipeline`, and colour the ones that escape.
+
+    `compute_bind_group_1` is everything but the pixels and the reference orbit, which are in
+    the second bind group.
+    */
+    pub fn compute(
+        &mut self,
+        device: &Arc<wgpu::Device>,
+        queue: &wgpu::Queue,
+        compute_pipeline: &wgpu::ComputePipeline,
+        compute_bind_group_1: &wgpu::BindGroup,
+        engine: Engine,
+        iteration_limit: u32,
+    ) {
+        let size = self.size;
+        debug_assert!(self.unescaped_pixels.len() <= size.width as usize * size.height as usize);
+        if cfg!(debug_assertions) {
+            for pixel in self.unescaped_pixels.iter() {
+                debug_assert!(pixel.escaped < 2);
+            }
+        }
+
+        // Keep the reference orbit ahead of the pixels.
+        if engine == Engine::Perturbation && self.reference_orbit.ensure_length(self.iterations + 2)
+        {
+            trace!(
+                "extended reference orbit to {} values",
+                self.reference_orbit.values().len()
+            );
+            std::mem::replace(
+                &mut self.reference_orbit_buffer,
+                create_reference_orbit_buffer(device, &self.reference_orbit),
+            )
+            .destroy();
+        }
+        // Nothing is iterated once every pixel has escaped or reached the limit.
+        if !self.unescaped_pixels.is_empty() {
+            self.iterations += 1;
+        }
+
+        let compute_bind_group_2 = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("compute-bind-group-2"),
+            layout: &compute_pipeline.get_bind_group_layout(1),
+            entries: &[
+                // compute.wgsl#input
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.pixels_buffers.input.binding_resource(0, None),
+                },
+                // compute.wgsl#output
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.pixels_buffers.output.binding_resource(0, None),
+                },
+                // compute.wgsl#reference_orbit
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: self.reference_orbit_buffer.binding_resource(0, None),
+                },
+            ],
+        });
+
+        let compute_command_buffer = command_buffer::create(
+            device,
+            &wgpu::CommandEncoderDescriptor::default(),
+            |command_encoder| {
+                self.pixels_buffers
+                    .input
+                    .write(queue, &self.unescaped_pixels);
+
+                command_encoder.push_debug_group("compute-pass");
+                command_encoder.with_compute_pass(
+                    &wgpu::ComputePassDescriptor {
+                        label: Some("compute-pass"),
+                    },
+                    |compute_pass| {
+                        compute_pass.set_pipeline(compute_pipeline);
+
+                        compute_pass.set_bind_group(0, compute_bind_group_1, &[]);
+                        compute_pass.set_bind_group(1, &compute_bind_group_2, &[]);
+
+                        compute_pass.insert_debug_marker("mandelbrot");
+
+                        let total_work = self.unescaped_pixels.len();
+
+                        let (x, y, z) = compute::mandelbrot_dispatch_size(total_work);
+
+                        compute_pass.dispatch_workgroups(x, y, z);
+                    },
+                );
+                command_encoder.pop_debug_group();
+
+                typed_buffer::copy_buffer_to_buffer(
+                    command_encoder,
+                    &self.pixels_buffers.output,
+                    0,
+                    &self.pixels_staging_buffer,
+                    0,
+                    self.unescaped_pixels.len().try_into().unwrap(),
+                );
+            },
+        );
+
+        queue.submit([compute_command_buffer]);
+
+        let pixels_staging_buffer_slice = self.pixels_staging_buffer.slice(..);
+
+        {
+            trace!("waiting for staging buffer");
+            let mapped = Arc::new((Mutex::new(true), Condvar::new()));
+
+            pixels_staging_buffer_slice.map_async(wgpu::MapMode::Read, {
+                let mapped = mapped.clone();
+                move |map_result| {
+                    debug!("map_async callback called");
+                    map_result.unwrap_or_else(|err| panic!("buffer async error: {}", err));
+                    let mut guard = mapped.0.lock().unwrap();
+                    *guard = false;
+                    mapped.1.notify_all();
+                }
+            });
+
+            {
+                let device = device.clone();
+                std::thread::spawn(move || while !device.poll(wgpu::Maintain::Poll) {});
+            }
+
+            debug!("waiting for condition");
+            let _guard = mapped
+                .1
+                .wait_while(mapped.0.lock().unwrap(), |pending| *pending)
+                .unwrap();
+            debug!("staging buffer mapped");
+        }
+
+        {
+            let pixels_staging_buffer_view: typed_buffer::View<Pixel> =
+                pixels_staging_buffer_slice.get_mapped_range();
+
+            let unescaped_pixels_len = self.unescaped_pixels.len();
+            self.unescaped_pixels.clear();
+            self.newly_escaped_pixels.clear();
+
+            pixels_staging_buffer_view
+                .iter()
+                /*
+                This caused a bug for me: even though I copy `unescaped_pixels.len()`
+                worth of data into the staging buffer, the buffer is still the size
+                of the screen.
+                Without the `take`, I was iterating over every pixel in the buffer.
+                Everything after `unescaped_pixels.len()` in the buffer is effectively
+                garbage (leftover from previous runs), but I was including it in the
+                `newly_escaped` array anyway.
+                */
+                .take(unescaped_pixels_len)
+                .for_each(|pixel| {
+                    let pixel = *pixel;
+
+                    debug_assert!(pixel.x < size.width);
+                    debug_assert!(pixel.y < size.height);
+                    debug_assert!(pixel.escaped < 2);
+
+                    if pixel.escaped == 1 {
+                        self.all_pixels
+                            [pixel.y as usize * size.width as usize + pixel.x as usize] = pixel;
+                        self.newly_escaped_pixels.push(pixel);
+                    } else if pixel.iteration_count >= iteration_limit {
+                        self.limited_pixels.push(pixel);
+                    } else {
+                        self.unescaped_pixels.push(pixel);
+                    }
+                });
+        }
+
+        self.pixels_staging_buffer.buffer().unmap();
+        self.pixels_buffers.swap();
+
+        self.histogram_colouring.update_colours(
+            size,
+            &self.all_pixels,
+            &self.newly_escaped_pixels,
+            &mut self.colour_ranges,
+        );
+    }
+
+    /// Copy the colours to the GPU, for [`Frame::colour_ranges_bind_group`].
+    pub fn write_colours(&self, queue: &wgpu::Queue) {
+        debug_assert!(
+            self.colour_ranges.len() == self.size.width as usize * self.size.height as usize,
+            "colour_ranges.len() == {}, expected {}",
+            self.colour_ranges.len(),
+            self.size.width * self.size.height,
+        );
+
+        self.colour_ranges_buffer.write(queue, &self.colour_ranges);
+    }
+
+    /// The second bind group of `render_pipeline`, which holds the colours.
+    pub fn colour_ranges_bind_group(
+        &self,
+        device: &wgpu::Device,
+        render_pipeline: &wgpu::RenderPipeline,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("render-bind-group-2"),
+            layout: &render_pipeline.get_bind_group_layout(1),
+            entries: &[
+                // render.wgsl#colour_ranges
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.colour_ranges_buffer.binding_resource(0, None),
+                },
+            ],
+        })
+    }
+}
@@ -0,0 +1,313 @@
+/*!
+Navigation history.
+
+Every view that's navigated to is recorded, so that it can be stepped back and forth through
+like a web browser's history. Each view keeps a small [`Preview`] of how it looked when it was
+left, so that it can be shown straight away while it's rendered again.
+*/
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{colour::ColourRange, pixel::Pixel, screen, view::View};
+
+/// Changes to the view less than this far apart are one step in the history, so that scrolling
+/// or dragging doesn't record every frame.
+const COALESCE_WITHIN: Duration = Duration::from_millis(500);
+
+/// The most steps that are kept, including the current view.
+const MAX_ENTRIES: usize = 100;
+
+/// How many pixels of the screen each side of a pixel of a [`Preview`] covers.
+const PREVIEW_DOWNSAMPLING: u32 = 8;
+
+/// A downsampled copy of the colours on screen.
+#[derive(Clone, Debug)]
+pub struct Preview {
+    /// The size of the screen that the preview was taken from.
+    screen_size: screen::Size,
+    size: screen::Size,
+    colour_ranges: Vec<ColourRange>,
+    iterations: u32,
+}
+
+impl Preview {
+    /// Take every [`PREVIEW_DOWNSAMPLING`]th pixel of `colour_ranges`, on a screen of `size`.
+    pub fn capture(
+        screen_size: screen::Size,
+        colour_ranges: &[ColourRange],
+        all_pixels: &[Pixel],
+    ) -> Self {
+        let size = screen::Size {
+            width: screen_size.width.div_ceil(PREVIEW_DOWNSAMPLING),
+            height: screen_size.height.div_ceil(PREVIEW_DOWNSAMPLING),
+        };
+        let colour_ranges = (0..size.height)
+            .flat_map(|y| {
+                (0..size.width).map(move |x| {
+                    colour_ranges[(y * PREVIEW_DOWNSAMPLING * screen_size.width
+                        + x * PREVIEW_DOWNSAMPLING) as usize]
+                })
+            })
+            .collect();
+        let iterations = all_pixels
+            .iter()
+            .filter(|pixel| pixel.escaped == 1)
+            .map(|pixel| pixel.iteration_count)
+            .max()
+            .unwrap_or(0);
+
+        Preview {
+            screen_size,
+            size,
+            colour_ranges,
+            iterations,
+        }
+    }
+
+    /// Stretch the preview over `colour_ranges`, on a screen of `size`.
+    pub fn draw(&self, screen_size: screen::Size, colour_ranges: &mut [ColourRange]) {
+        for y in 0..screen_size.height {
+            for x in 0..screen_size.width {
+                // The pixel of the screen that the preview was taken from.
+                let source_x = x as u64 * self.screen_size.width as u64 / screen_size.width as u64;
+                let source_y =
+                    y as u64 * self.screen_size.height as u64 / screen_size.height as u64;
+                let preview_x = source_x / PREVIEW_DOWNSAMPLING as u64;
+                let preview_y = source_y / PREVIEW_DOWNSAMPLING as u64;
+                colour_ranges[(y * screen_size.width + x) as usize] =
+                    self.colour_ranges[(preview_y * self.size.width as u64 + preview_x) as usize];
+            }
+        }
+    }
+
+    /**
+    How many iterations the pixels had been through when the preview was taken.
+
+    Once the view has been rendered again for as long, every pixel that the preview shows as
+    escaped has escaped again, and what's left of the preview is out of date.
+    */
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+}
+
+/// A view in the history.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub view: View,
+    /// How the view looked when it was last left, if it's been left.
+    pub preview: Option<Preview>,
+}
+
+/**
+The views that have been navigated to, oldest first.
+
+`preview` arguments are the current view's [`Preview`], which is only captured when the current
+view is about to be left.
+*/
+pub struct History {
+    home: View,
+    entries: VecDeque<Entry>,
+    current: usize,
+    last_visit: Option<Instant>,
+}
+
+impl History {
+    /// Start the history at `home`.
+    pub fn new(home: View) -> Self {
+        History {
+            home: home.clone(),
+            entries: VecDeque::from([Entry {
+                view: home,
+                preview: None,
+            }]),
+            current: 0,
+            last_visit: None,
+        }
+    }
+
+    /**
+    Record that the view has changed to `view`, forgetting any steps that could be redone.
+
+    If the view last changed less than [`COALESCE_WITHIN`] ago, `view` replaces the current
+    step instead.
+    */
+    pub fn visit(&mut self, view: View, preview: impl FnOnce() -> Preview) {
+        self.visit_at(view, Instant::now(), preview);
+    }
+
+    /// [`History::visit`], with the view changing at `now`.
+    fn visit_at(&mut self, view: View, now: Instant, preview: impl FnOnce() -> Preview) {
+        let coalesce = self
+            .last_visit
+            .is_some_and(|last_visit| now.duration_since(last_visit) < COALESCE_WITHIN);
+
+        if coalesce {
+            self.entries[self.current].view = view;
+        } else {
+            self.push(view, preview());
+        }
+        // After `push`, which ends the window.
+        self.last_visit = Some(now);
+    }
+
+    /// Step back, returning the view to show, or `None` if there's nothing to step back to.
+    pub fn undo(&mut self, preview: Preview) -> Option<Entry> {
+        if self.current == 0 {
+            return None;
+        }
+        self.entries[self.current].preview = Some(preview);
+        self.current -= 1;
+        self.last_visit = None;
+        Some(self.entries[self.current].clone())
+    }
+
+    /// Step forward again after [`History::undo`], returning the view to show, or `None` if
+    /// there's nothing to step forward to.
+    pub fn redo(&mut self, preview: Preview) -> Option<Entry> {
+        if self.current + 1 == self.entries.len() {
+            return None;
+        }
+        self.entries[self.current].preview = Some(preview);
+        self.current += 1;
+        self.last_visit = None;
+        Some(self.entries[self.current].clone())
+    }
+
+    /// Go back to the view that the history started at, as a new step, returning it.
+    pub fn home(&mut self, preview: Preview) -> Entry {
+        let home_preview = self
+            .entries
+            .iter()
+            .find(|entry| entry.view == self.home)
+            .and_then(|entry| entry.preview.clone());
+        self.push(self.home.clone(), preview);
+
+        let entry = &mut self.entries[self.current];
+        entry.preview = home_preview;
+        entry.clone()
+    }
+
+    /// Forget every preview, after something other than the view has changed what's on screen.
+    pub fn clear_previews(&mut self) {
+        for entry in &mut self.entries {
+            entry.preview = None;
+        }
+    }
+
+    /// Record that the view has changed to `view` as a new step, however recently it last changed,
+    /// forgetting any steps that could be redone.
+    pub fn push(&mut self, view: View, preview: Preview) {
+        self.last_visit = None;
+
+        self.entries[self.current].preview = Some(preview);
+        self.entries.truncate(self.current + 1);
+        self.entries.push_back(Entry {
+            view,
+            preview: None,
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.current = self.entries.len() - 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A view centered on `real`.
+    fn view(real: i32) -> View {
+        format!("{} 0 1", real).parse().unwrap()
+    }
+
+    fn preview() -> Preview {
+        let size = screen::Size {
+            width: 1,
+            height: 1,
+        };
+        Preview::capture(size, &[ColourRange::default()], &[])
+    }
+
+    fn current(history: &History) -> &View {
+        &history.entries[history.current].view
+    }
+
+    #[test]
+    fn visits_within_the_coalescing_window_are_one_step() {
+        let start = Instant::now();
+        let mut history = History::new(view(0));
+        history.visit_at(view(1), start, preview);
+        history.visit_at(view(2), start + COALESCE_WITHIN / 2, preview);
+        assert_eq!(history.entries.len(), 2);
+        assert_eq!(current(&history), &view(2));
+
+        history.visit_at(view(3), start + COALESCE_WITHIN * 2, preview);
+        assert_eq!(history.entries.len(), 3);
+        assert_eq!(history.undo(preview()).unwrap().view, view(2));
+        assert_eq!(history.undo(preview()).unwrap().view, view(0));
+        assert!(history.undo(preview()).is_none());
+    }
+
+    #[test]
+    fn undo_and_redo_step_through_the_history() {
+        let mut history = History::new(view(0));
+        history.push(view(1), preview());
+        history.push(view(2), preview());
+
+        assert!(history.redo(preview()).is_none());
+        let entry = history.undo(preview()).unwrap();
+        assert_eq!(entry.view, view(1));
+        assert!(entry.preview.is_some());
+        assert_eq!(history.undo(preview()).unwrap().view, view(0));
+        assert_eq!(history.redo(preview()).unwrap().view, view(1));
+        assert_eq!(history.redo(preview()).unwrap().view, view(2));
+        assert!(history.redo(preview()).is_none());
+    }
+
+    #[test]
+    fn visiting_after_undo_forgets_the_redo_steps() {
+        let mut history = History::new(view(0));
+        history.push(view(1), preview());
+        history.push(view(2), preview());
+        history.undo(preview());
+        history.undo(preview());
+
+        // `undo` ends the coalescing window, so this is a new step.
+        history.visit(view(3), preview);
+        assert_eq!(history.entries.len(), 2);
+        assert!(history.redo(preview()).is_none());
+        assert_eq!(history.undo(preview()).unwrap().view, view(0));
+    }
+
+    #[test]
+    fn home_is_a_new_step() {
+        let mut history = History::new(view(0));
+        history.push(view(1), preview());
+
+        let entry = history.home(preview());
+        assert_eq!(entry.view, view(0));
+        // The starting view was left, so its preview is reused.
+        assert!(entry.preview.is_some());
+        assert_eq!(history.entries.len(), 3);
+        assert_eq!(history.undo(preview()).unwrap().view, view(1));
+    }
+
+    #[test]
+    fn oldest_steps_are_dropped() {
+        let mut history = History::new(view(0));
+        for real in 1..=MAX_ENTRIES as i32 {
+            history.push(view(real), preview());
+        }
+        assert_eq!(history.entries.len(), MAX_ENTRIES);
+        assert_eq!(history.entries[0].view, view(1));
+        assert_eq!(current(&history), &view(MAX_ENTRIES as i32));
+
+        while history.undo(preview()).is_some() {}
+        assert_eq!(current(&history), &view(1));
+    }
+}
@@ -0,0 +1,485 @@
+/*!
+The heads-up display: lines of text drawn over the top left of the screen by `hud.wgsl`.
+
+Text is drawn in a built-in 5 by 7 pixel font of the ASCII characters from ` ` to `_`, which has
+no lowercase letters, so lowercase letters are shown in uppercase. Other characters are shown as
+`?`.
+*/
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{command_encoder::CommandEncoderExt, screen, typed_buffer};
+
+/// The most characters shown on each line, after which lines are cut short.
+pub const MAX_COLUMNS: u32 = 128;
+
+/// The most lines shown, after which the rest are left out.
+pub const MAX_ROWS: u32 = 16;
+
+/// The first character in [`FONT`].
+const FIRST_CHARACTER: u8 = b' ';
+
+/**
+A glyph for each character from ` ` to `_`, in ASCII order, as `hud.wgsl#font` expects.
+
+Each glyph is 7 rows from the top, with the leftmost pixel of each row in bit 4.
+*/
+const FONT: [[u8; 7]; 64] = [
+    [
+        0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000,
+    ], // ` `
+    [
+        0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00000, 0b00100,
+    ], // `!`
+    [
+        0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000,
+    ], // `"`
+    [
+        0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010,
+    ], // `#`
+    [
+        0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100,
+    ], // `$`
+    [
+        0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011,
+    ], // `%`
+    [
+        0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101,
+    ], // `&`
+    [
+        0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000,
+    ], // `'`
+    [
+        0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010,
+    ], // `(`
+    [
+        0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000,
+    ], // `)`
+    [
+        0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000,
+    ], // `*`
+    [
+        0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000,
+    ], // `+`
+    [
+        0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000,
+    ], // `,`
+    [
+        0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000,
+    ], // `-`
+    [
+        0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100,
+    ], // `.`
+    [
+        0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000,
+    ], // `/`
+    [
+        0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110,
+    ], // `0`
+    [
+        0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+    ], // `1`
+    [
+        0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111,
+    ], // `2`
+    [
+        0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110,
+    ], // `3`
+    [
+        0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010,
+    ], // `4`
+    [
+        0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110,
+    ], // `5`
+    [
+        0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110,
+    ], // `6`
+    [
+        0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000,
+    ], // `7`
+    [
+        0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110,
+    ], // `8`
+    [
+        0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100,
+    ], // `9`
+    [
+        0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000,
+    ], // `:`
+    [
+        0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000,
+    ], // `;`
+    [
+        0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010,
+    ], // `<`
+    [
+        0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000,
+    ], // `=`
+    [
+        0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000,
+    ], // `>`
+    [
+        0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100,
+    ], // `?`
+    [
+        0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110,
+    ], // `@`
+    [
+        0b01110, 0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001,
+    ], // `A`
+    [
+        0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110,
+    ], // `B`
+    [
+        0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110,
+    ], // `C`
+    [
+        0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100,
+    ], // `D`
+    [
+        0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111,
+    ], // `E`
+    [
+        0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000,
+    ], // `F`
+    [
+        0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111,
+    ], // `G`
+    [
+        0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001,
+    ], // `H`
+    [
+        0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110,
+    ], // `I`
+    [
+        0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100,
+    ], // `J`
+    [
+        0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001,
+    ], // `K`
+    [
+        0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111,
+    ], // `L`
+    [
+        0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001,
+    ], // `M`
+    [
+        0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001,
+    ], // `N`
+    [
+        0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+    ], // `O`
+    [
+        0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000,
+    ], // `P`
+    [
+        0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101,
+    ], // `Q`
+    [
+        0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001,
+    ], // `R`
+    [
+        0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110,
+    ], // `S`
+    [
+        0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100,
+    ], // `T`
+    [
+        0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110,
+    ], // `U`
+    [
+        0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100,
+    ], // `V`
+    [
+        0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010,
+    ], // `W`
+    [
+        0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001,
+    ], // `X`
+    [
+        0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100,
+    ], // `Y`
+    [
+        0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111,
+    ], // `Z`
+    [
+        0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110,
+    ], // `[`
+    [
+        0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000,
+    ], // `\`
+    [
+        0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110,
+    ], // `]`
+    [
+        0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000,
+    ], // `^`
+    [
+        0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111,
+    ], // `_`
+];
+
+/// The font, as `hud.wgsl#font`.
+pub fn font() -> Vec<u32> {
+    FONT.iter().flatten().map(|row| *row as u32).collect()
+}
+
+/// The index in [`FONT`] of the glyph for `character`.
+fn glyph(character: char) -> u32 {
+    let character = character.to_ascii_uppercase();
+    match u8::try_from(character) {
+        Ok(byte) if (FIRST_CHARACTER..FIRST_CHARACTER + FONT.len() as u8).contains(&byte) => {
+            (byte - FIRST_CHARACTER) as u32
+        }
+        _ => (b'?' - FIRST_CHARACTER) as u32,
+    }
+}
+
+/// Lines of text, laid out in a grid of glyphs for `hud.wgsl`.
+pub struct Text {
+    /// The number of columns and rows that are used, as `hud.wgsl#text_size`.
+    pub size: [u32; 2],
+    /// [`MAX_COLUMNS`] glyphs for each of [`MAX_ROWS`] rows, as `hud.wgsl#text`.
+    pub glyphs: Vec<u32>,
+}
+
+impl Text {
+    pub fn new<S: AsRef<str>>(lines: &[S]) -> Self {
+        let mut glyphs = vec![glyph(' '); (MAX_COLUMNS * MAX_ROWS) as usize];
+        let mut size = [0, 0];
+
+        for (row, line) in lines.iter().take(MAX_ROWS as usize).enumerate() {
+            let start = row * MAX_COLUMNS as usize;
+            let mut columns = 0;
+            for (cell, character) in glyphs[start..start + MAX_COLUMNS as usize]
+                .iter_mut()
+                .zip(line.as_ref().chars())
+            {
+                *cell = glyph(character);
+                columns += 1;
+            }
+            size = [size[0].max(columns), row as u32 + 1];
+        }
+
+        Text { size, glyphs }
+    }
+}
+
+/// Draws [`Text`] over the screen with `hud.wgsl`.
+pub struct Renderer {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    text_size_buffer: typed_buffer::var::Var<[u32; 2]>,
+    text_buffer: typed_buffer::Buffer<u32>,
+    /// Only used by `bind_group`.
+    _font_buffer: typed_buffer::Buffer<u32>,
+}
+
+impl Renderer {
+    /// Draw into textures of `format`, on a screen of the size in `screen_size_buffer`.
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        screen_size_buffer: &typed_buffer::var::Var<screen::Size>,
+    ) -> Self {
+        let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("hud-shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("hud.wgsl").into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("hud-bind-group-layout"),
+            entries: &[
+                // hud.wgsl#screen_size
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // hud.wgsl#text_size
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // hud.wgsl#text
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // hud.wgsl#font
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("hud-pipeline-layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("hud-pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader_module,
+                entry_point: "vertex_main",
+                buffers: &[],
+            },
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                unclipped_depth: false,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &shader_module,
+                entry_point: "fragment_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    // The background is translucent.
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        let text_size_buffer = typed_buffer::var::Builder::new([0u32; 2])
+            .with_label("hud-text-size-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
+            .create(device);
+        let text_buffer: typed_buffer::Buffer<u32> =
+            typed_buffer::Builder::new((MAX_COLUMNS * MAX_ROWS) as u64)
+                .with_label("hud-text-buffer")
+                .with_usage(wgpu::BufferUsages::STORAGE)
+                .create(device);
+        let font_buffer = typed_buffer::Builder::from(font().as_slice())
+            .with_label("hud-font-buffer")
+            .with_usage(wgpu::BufferUsages::STORAGE)
+            .create(device);
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("hud-bind-group"),
+            layout: &bind_group_layout,
+            entries: &[
+                // hud.wgsl#screen_size
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: screen_size_buffer.binding_resource(),
+                },
+                // hud.wgsl#text_size
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: text_size_buffer.binding_resource(),
+                },
+                // hud.wgsl#text
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: text_buffer.binding_resource(0, None),
+                },
+                // hud.wgsl#font
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: font_buffer.binding_resource(0, None),
+                },
+            ],
+        });
+
+        Renderer {
+            pipeline,
+            bind_group,
+            text_size_buffer,
+            text_buffer,
+            _font_buffer: font_buffer,
+        }
+    }
+
+    /// Show `text` from the next [`Renderer::draw`].
+    pub fn write(&self, queue: &wgpu::Queue, text: &Text) {
+        self.text_size_buffer.write(queue, text.size);
+        self.text_buffer.write(queue, &text.glyphs);
+    }
+
+    /// Draw the text over what's already in `view`.
+    pub fn draw(&self, command_encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView) {
+        command_encoder.push_debug_group("hud-pass");
+        command_encoder.with_render_pass(
+            &wgpu::RenderPassDescriptor {
+                label: Some("hud-pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            },
+            |render_pass| {
+                render_pass.set_pipeline(&self.pipeline);
+                render_pass.set_bind_group(0, &self.bind_group, &[]);
+                render_pass.draw(0..4, 0..1);
+            },
+        );
+        command_encoder.pop_debug_group();
+    }
+}
+
+/// How many frames were drawn in the last second.
+#[derive(Default)]
+pub struct FrameRate {
+    frames: VecDeque<Instant>,
+}
+
+impl FrameRate {
+    /// Count a frame drawn at `now`.
+    pub fn tick(&mut self, now: Instant) {
+        self.frames.push_back(now);
+        while self
+            .frames
+            .front()
+            .is_some_and(|frame| now.duration_since(*frame) > Duration::from_secs(1))
+        {
+            self.frames.pop_front();
+        }
+    }
+
+    pub fn per_second(&self) -> usize {
+        self.frames.len()
+    }
+}
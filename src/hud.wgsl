@@ -0,0 +1,64 @@
+// Corresponds to `hud.rs#MAX_COLUMNS`.
+let MAX_COLUMNS = 128u;
+// The size of a glyph in `font`, in font pixels.
+let GLYPH_SIZE = vec2<u32>(5u, 7u);
+// The space taken by each character, including the gaps between them, in font pixels.
+let CELL_SIZE = vec2<u32>(6u, 9u);
+// The space around the text, inside the background, in font pixels.
+let PADDING = 2u;
+// How many screen pixels wide each font pixel is.
+let SCALE = 2u;
+// The space between the edges of the screen and the background, in screen pixels.
+let MARGIN = 8u;
+let BACKGROUND = vec4<f32>(0.0, 0.0, 0.0, 0.6);
+let FOREGROUND = vec4<f32>(1.0, 1.0, 1.0, 1.0);
+
+@group(0) @binding(0) var<uniform> screen_size : vec2<u32>;
+// How many columns and rows of `text` are used.
+@group(0) @binding(1) var<uniform> text_size : vec2<u32>;
+// The index in `font` of each character, in rows of `MAX_COLUMNS`.
+@group(0) @binding(2) var<storage, read> text : array<u32>;
+// Rows of 7 for each glyph, from the top, with the leftmost pixel in bit 4.
+@group(0) @binding(3) var<storage, read> font : array<u32>;
+
+// The bottom right corner of the background, in screen pixels.
+fn background_end() -> vec2<u32> {
+  return MARGIN + (text_size * CELL_SIZE + 2u * PADDING) * SCALE;
+}
+
+// Covers the background, in the same order as `render.wgsl#vertex_main`.
+@vertex
+fn vertex_main(@builtin(vertex_index) index : u32) -> @builtin(position) vec4<f32> {
+  let start = vec2<f32>(f32(MARGIN), f32(MARGIN));
+  let end = vec2<f32>(background_end());
+  var corners = array<vec2<f32>, 4>(
+    vec2<f32>(start.x, end.y),
+    vec2<f32>(end.x, end.y),
+    vec2<f32>(start.x, start.y),
+    vec2<f32>(end.x, start.y)
+  );
+
+  let position = corners[index] / vec2<f32>(screen_size) * 2.0 - 1.0;
+  return vec4<f32>(position.x, -position.y, 0.0, 1.0);
+}
+
+@fragment
+fn fragment_main(@builtin(position) position : vec4<f32>) -> @location(0) vec4<f32> {
+  let pixel = (vec2<u32>(position.xy) - MARGIN) / SCALE;
+  if any(pixel < vec2<u32>(PADDING, PADDING)) {
+    return BACKGROUND;
+  }
+
+  let cell = (pixel - PADDING) / CELL_SIZE;
+  let offset = (pixel - PADDING) % CELL_SIZE;
+  if any(cell >= text_size) || any(offset >= GLYPH_SIZE) {
+    return BACKGROUND;
+  }
+
+  let glyph = text[cell.y * MAX_COLUMNS + cell.x];
+  let row = font[glyph * GLYPH_SIZE.y + offset.y];
+  if (row & (1u << (GLYPH_SIZE.x - 1u - offset.x))) != 0u {
+    return FOREGROUND;
+  }
+  return BACKGROUND;
+}
@@ -0,0 +1,264 @@
+/*!
+[Kalles Fraktaler](https://mathr.co.uk/kf/kf.html) `.kfr` location files.
+
+A `.kfr` file is a list of `Key: value` lines, of which only these are used:
+
+```text
+Re: -0.743643887037158704752191506114774
+Im: 0.131825904205311970493132056385139
+Zoom: 2.5E25
+Iterations: 65536
+FractalType: 0
+Power: 2
+```
+
+`Zoom: 1` shows from `-2` to `2` vertically, which is the same as [`View::zoom`]. Colouring isn't
+converted, because Kalles Fraktaler's colouring works differently.
+*/
+
+use std::{fmt::Display, str::FromStr};
+
+use dashu_float::DBig;
+
+use crate::{bookmarks::Bookmark, formula::Formula, view::View};
+
+/// Read the location in a `.kfr` file, as a bookmark called `name`.
+pub fn parse(name: &str, source: &str) -> Result<Bookmark, ParseKfrError> {
+    let field = |key: &'static str| {
+        source
+            .lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(line_key, _)| line_key.trim() == key)
+            .map(|(_, value)| value.trim())
+    };
+    let required = |key: &'static str| field(key).ok_or(ParseKfrError::MissingField(key));
+    let invalid = |key: &'static str, value: &str| ParseKfrError::InvalidField {
+        key,
+        value: value.to_string(),
+    };
+
+    let real = required("Re")?;
+    let real = DBig::from_str(real).map_err(|_| invalid("Re", real))?;
+    let imaginary = required("Im")?;
+    let imaginary = DBig::from_str(imaginary).map_err(|_| invalid("Im", imaginary))?;
+    let zoom = required("Zoom")?;
+    let zoom = zoom
+        .parse::<f64>()
+        .ok()
+        .filter(|zoom| zoom.is_finite() && *zoom > 0.0)
+        .ok_or_else(|| invalid("Zoom", zoom))?;
+
+    let iteration_limit = field("Iterations")
+        .map(|iterations| {
+            iterations
+                .parse::<u32>()
+                .ok()
+                .filter(|iterations| *iterations > 0)
+                .ok_or_else(|| invalid("Iterations", iterations))
+        })
+        .transpose()?;
+
+    let fractal_type = field("FractalType")
+        .map(|fractal_type| {
+            fractal_type
+                .parse::<u32>()
+                .map_err(|_| invalid("FractalType", fractal_type))
+        })
+        .transpose()?
+        .unwrap_or(0);
+    let formula = match fractal_type {
+        0 => Formula::Multibrot,
+        1 => Formula::BurningShip,
+        3 => Formula::Celtic,
+        4 => Formula::Tricorn,
+        5 => Formula::CelticMandelbar,
+        _ => return Err(ParseKfrError::UnsupportedFractalType(fractal_type)),
+    };
+    let exponent = field("Power")
+        .map(|power| {
+            power
+                .parse::<u32>()
+                .ok()
+                .filter(|power| *power >= 2)
+                .ok_or_else(|| invalid("Power", power))
+        })
+        .transpose()?
+        .unwrap_or(2);
+
+    Ok(Bookmark {
+        name: name.to_string(),
+        view: View::new(real, imaginary, zoom),
+        formula: Some(formula),
+        exponent: Some(exponent as f32),
+        iteration_limit,
+        palette: None,
+    })
+}
+
+/// Write `bookmark` as a `.kfr` file.
+pub fn write(bookmark: &Bookmark) -> Result<String, WriteKfrError> {
+    let formula = bookmark.formula.unwrap_or_default();
+    let fractal_type = match formula {
+        Formula::Multibrot => 0,
+        Formula::BurningShip => 1,
+        Formula::Celtic => 3,
+        Formula::Tricorn => 4,
+        Formula::CelticMandelbar => 5,
+        _ => return Err(WriteKfrError::UnsupportedFormula(formula)),
+    };
+    let exponent = bookmark.exponent.unwrap_or(2.0);
+    if !(exponent >= 2.0 && exponent.fract() == 0.0) {
+        return Err(WriteKfrError::UnsupportedExponent(exponent));
+    }
+
+    let mut kfr = format!(
+        "Re: {}\r\nIm: {}\r\nZoom: {:E}\r\n",
+        bookmark.view.real(),
+        bookmark.view.imaginary(),
+        bookmark.view.zoom()
+    );
+    if let Some(iteration_limit) = bookmark.iteration_limit {
+        kfr += &format!("Iterations: {}\r\n", iteration_limit);
+    }
+    kfr += &format!("FractalType: {}\r\nPower: {}\r\n", fractal_type, exponent);
+    Ok(kfr)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseKfrError {
+    MissingField(&'static str),
+    InvalidField { key: &'static str, value: String },
+    UnsupportedFractalType(u32),
+}
+
+impl Display for ParseKfrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseKfrError::MissingField(key) => write!(f, "missing {:?}", key),
+            ParseKfrError::InvalidField { key, value } => {
+                write!(f, "invalid {:?}: {:?}", key, value)
+            }
+            ParseKfrError::UnsupportedFractalType(fractal_type) => {
+                write!(f, "unsupported fractal type: {}", fractal_type)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseKfrError {}
+
+#[derive(Debug, PartialEq)]
+pub enum WriteKfrError {
+    UnsupportedFormula(Formula),
+    UnsupportedExponent(f32),
+}
+
+impl Display for WriteKfrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteKfrError::UnsupportedFormula(formula) => write!(
+                f,
+                "Kalles Fraktaler doesn't have the {} formula",
+                formula.name()
+            ),
+            WriteKfrError::UnsupportedExponent(exponent) => write!(
+                f,
+                "Kalles Fraktaler only supports integer exponents from 2, not {}",
+                exponent
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WriteKfrError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_files_parse_back() {
+        for (formula, exponent) in [
+            (Formula::Multibrot, 2.0),
+            (Formula::BurningShip, 3.0),
+            (Formula::Tricorn, 2.0),
+            (Formula::Celtic, 4.0),
+            (Formula::CelticMandelbar, 2.0),
+        ] {
+            let bookmark = Bookmark {
+                name: "deep".to_string(),
+                view: "-0.74364388703715870475219150611 0.13182590420531197049313205638 2.5e25"
+                    .parse()
+                    .unwrap(),
+                formula: Some(formula),
+                exponent: Some(exponent),
+                iteration_limit: Some(65536),
+                palette: None,
+            };
+
+            let parsed = parse("deep", &write(&bookmark).unwrap()).unwrap();
+            assert_eq!(parsed.name, "deep");
+            assert_eq!(parsed.view, bookmark.view);
+            assert_eq!(parsed.formula, Some(formula));
+            assert_eq!(parsed.exponent, Some(exponent));
+            assert_eq!(parsed.iteration_limit, Some(65536));
+        }
+    }
+
+    #[test]
+    fn unsupported_settings_cant_be_written() {
+        let mut bookmark = Bookmark {
+            name: "a".to_string(),
+            view: View::default(),
+            formula: Some(Formula::Multibrot),
+            exponent: Some(2.5),
+            iteration_limit: None,
+            palette: None,
+        };
+        assert_eq!(
+            write(&bookmark),
+            Err(WriteKfrError::UnsupportedExponent(2.5))
+        );
+        bookmark.exponent = None;
+        bookmark.formula = Some(Formula::CelticBurningShip);
+        assert_eq!(
+            write(&bookmark),
+            Err(WriteKfrError::UnsupportedFormula(
+                Formula::CelticBurningShip
+            ))
+        );
+    }
+
+    #[test]
+    fn invalid_files_are_rejected() {
+        assert_eq!(
+            parse("a", "Re: 0\nZoom: 1\n").unwrap_err(),
+            ParseKfrError::MissingField("Im")
+        );
+        for (key, value) in [
+            ("Zoom", "0"),
+            ("Zoom", "-1"),
+            ("Iterations", "0"),
+            ("Power", "1"),
+        ] {
+            let mut fields = vec![("Re", "0"), ("Im", "0"), ("Zoom", "1")];
+            fields.retain(|(field, _)| *field != key);
+            fields.push((key, value));
+            let source = fields
+                .iter()
+                .map(|(key, value)| format!("{}: {}\r\n", key, value))
+                .collect::<String>();
+            assert_eq!(
+                parse("a", &source).unwrap_err(),
+                ParseKfrError::InvalidField {
+                    key,
+                    value: value.to_string()
+                }
+            );
+        }
+        assert_eq!(
+            parse("a", "Re: 0\nIm: 0\nZoom: 1\nFractalType: 2\n").unwrap_err(),
+            ParseKfrError::UnsupportedFractalType(2)
+        );
+    }
+}
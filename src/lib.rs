@@ -1,7 +1,24 @@
+pub mod bindings;
+pub mod bookmarks;
 pub mod colour;
 pub mod command_buffer;
 pub mod command_encoder;
 pub mod compute;
+pub mod custom_formula;
+pub mod double_single;
+pub mod formula;
+pub mod history;
+pub mod hud;
+pub mod kfr;
+pub mod par;
+pub mod perturbation;
 pub mod pixel;
+pub mod scene;
 pub mod screen;
+pub mod screenshot;
+pub mod status;
+#[cfg(test)]
+pub mod test_support;
+pub mod transition;
 pub mod typed_buffer;
+pub mod view;
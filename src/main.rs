@@ -1,58 +1,128 @@
-use std::sync::{Arc, Condvar, Mutex};
+mod cli;
+mod frame;
+
+use std::{
+    fmt::Display,
+    io::IsTerminal,
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
 use bytemuck::{Pod, Zeroable};
+use clap::Parser;
 use log::{debug, trace};
 use rayon::ThreadPoolBuilder;
 use winit::{
-    event::{Event, WindowEvent},
+    dpi::PhysicalSize,
+    event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    window::{Fullscreen, WindowBuilder},
 };
 
 use wgpu_mandelbrot::{
-    colour::{ColourRange, HistogramColouring},
+    bindings::{Bindings, Command},
+    bookmarks::{self, Bookmark},
     command_buffer,
     command_encoder::CommandEncoderExt,
-    compute,
+    compute::{self, Engine},
+    custom_formula::CustomFormula,
+    double_single::{DoubleSingle, DoubleSingleComplex},
+    formula::{Formula, Polynomial},
+    history::{Entry, History, Preview},
+    hud::{self, FrameRate},
+    kfr, par,
+    perturbation::{Scaled, SeriesApproximation},
     pixel::{Complex, Pixel},
-    screen, typed_buffer,
+    scene::{self, Scene},
+    screen,
+    screenshot::{self, Image},
+    status::{self, Progress},
+    transition::{self, Transition},
+    typed_buffer,
+    view::View,
 };
 
-fn create_pixels(size: screen::Size) -> Vec<Pixel> {
-    (0..size.height)
-        .flat_map(move |y| {
-            (0..size.width).map(move |x| Pixel {
-                x: x as u32,
-                y: y as u32,
-                current_value: Complex::ZERO,
-                escaped: 0,
-                iteration_count: 0,
-            })
-        })
-        .collect::<Vec<_>>()
+use crate::{
+    cli::{Cli, Mode},
+    frame::Frame,
+};
+
+/// [`std::eprintln`], after clearing the status line so that the two don't share a line.
+macro_rules! eprintln {
+    ($($arg:tt)*) => {{
+        status::clear_line();
+        std::eprintln!($($arg)*);
+    }};
+}
+
+/// [`std::eprint`], after clearing the status line so that the two don't share a line.
+macro_rules! eprint {
+    ($($arg:tt)*) => {{
+        status::clear_line();
+        std::eprint!($($arg)*);
+    }};
 }
 
-fn create_pixels_buffers(
+/// Create the module for `compute.wgsl`, using `custom_formula` for [`Formula::Custom`].
+fn create_compute_shader_module(
     device: &wgpu::Device,
-    size: screen::Size,
-) -> typed_buffer::DoubleBuffer<Pixel> {
-    let pixels = create_pixels(size);
-
-    typed_buffer::DoubleBuffer {
-        input: typed_buffer::Builder::from(pixels.as_slice())
-            .with_label("pixels_buffer_1")
-            .with_usage(wgpu::BufferUsages::STORAGE)
-            .with_usage(wgpu::BufferUsages::COPY_SRC)
-            .create(device),
-
-        output: typed_buffer::Builder::from(pixels.as_slice())
-            .with_label("pixels_buffer_2")
-            .with_usage(wgpu::BufferUsages::STORAGE)
-            .with_usage(wgpu::BufferUsages::COPY_SRC)
-            .create(device),
+    custom_formula: Option<&CustomFormula>,
+) -> wgpu::ShaderModule {
+    let source = custom_formula.map_or(compute::SHADER_SOURCE, CustomFormula::shader_source);
+
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("compute-shader"),
+        source: wgpu::ShaderSource::Wgsl(source.into()),
+    })
+}
+
+/// `compute_f64_shader_module` is only used for [`Engine::Float64`].
+fn create_compute_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    compute_shader_module: &wgpu::ShaderModule,
+    compute_f64_shader_module: Option<&wgpu::ShaderModule>,
+    engine: Engine,
+) -> wgpu::ComputePipeline {
+    let module = match engine {
+        Engine::Float64 => compute_f64_shader_module.expect("SHADER_FLOAT64 is not enabled"),
+        _ => compute_shader_module,
+    };
+
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("compute-pipeline"),
+        layout: Some(layout),
+        module,
+        entry_point: engine.entry_point(),
+    })
+}
+
+/// Parse an environment variable if it's set, exiting with an error message if it's invalid.
+fn parse_env_var<A: FromStr>(name: &str) -> Option<A>
+where
+    A::Err: Display,
+{
+    let value = std::env::var(name).ok()?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(err) => {
+            eprintln!("error: invalid {}: {}", name, err);
+            std::process::exit(1)
+        }
     }
 }
 
+/// Warn that `engine` can't tell neighbouring pixels apart, so the image will be blocky.
+fn warn_pixelated(engine: Engine) {
+    eprintln!(
+        "warning: the view is too deep for the {} engine ({}), so it will be pixelated",
+        engine.name(),
+        engine.precision()
+    );
+}
+
 #[repr(C)]
 #[derive(Pod, Zeroable, Clone, Copy, Debug)]
 struct Vec2 {
@@ -60,52 +130,391 @@ struct Vec2 {
     y: f32,
 }
 
+/// How far the cursor has to move before a click becomes a drag.
+const DRAG_THRESHOLD: f32 = 4.0;
+
+/// `render.wgsl#selection` when there's no box.
+const NO_SELECTION: [f32; 4] = [0.0; 4];
+
+/**
+The box to zoom into when dragging from `start` to `end` on a screen of `size`, as
+`render.wgsl#selection`.
+
+The box has a corner at `start`, and is the smallest box with the same aspect ratio as the screen
+that reaches `end`, so that it's exactly what the screen will show.
+*/
+fn selection(start: Vec2, end: Vec2, size: screen::Size) -> [f32; 4] {
+    let (width, height) = (size.width as f32, size.height as f32);
+    let fraction = ((end.x - start.x).abs() / width).max((end.y - start.y).abs() / height);
+    [
+        start.x,
+        start.y,
+        start.x + (end.x - start.x).signum() * fraction * width,
+        start.y + (end.y - start.y).signum() * fraction * height,
+    ]
+}
+
+/// Write the uniforms that describe `view`.
+fn write_view(
+    queue: &wgpu::Queue,
+    view: &View,
+    zoom_buffer: &typed_buffer::var::Var<f32>,
+    scale_buffer: &typed_buffer::var::Var<Scaled>,
+    double_single_scale_buffer: &typed_buffer::var::Var<DoubleSingle>,
+    origin_buffer: &typed_buffer::var::Var<DoubleSingleComplex>,
+) {
+    zoom_buffer.write(queue, view.zoom() as f32);
+    scale_buffer.write(queue, Scaled::from(view.scale()));
+    double_single_scale_buffer.write(queue, DoubleSingle::from(view.scale()));
+    origin_buffer.write(queue, DoubleSingleComplex::from(&view.center()));
+}
+
+/// Warn that zooming in was refused, because even `engine` can't tell the pixels apart.
+fn warn_zoom_limited(engine: Engine) {
+    eprintln!(
+        "warning: can't zoom in any further with the {} engine ({})",
+        engine.name(),
+        engine.precision()
+    );
+}
+
+/// The initial [`Command::IncreaseIterationLimit`] and [`Command::DecreaseIterationLimit`] step
+/// through powers of two from here.
+const DEFAULT_ITERATION_LIMIT: u32 = 1 << 16;
+
+/// How far [`Command::PanLeft`] and friends move the view, as a fraction of the screen.
+const KEY_PAN_FRACTION: f64 = 0.1;
+
+/// How much [`Command::ZoomIn`] and [`Command::ZoomOut`] zoom by.
+const KEY_ZOOM_FACTOR: f64 = 1.5;
+
+/// How often the window title and status line are updated while the view is rendering.
+const STATUS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The left mouse button, which clicks to center the image on a point, and drags to pan.
+#[derive(Clone, Copy, Debug)]
+enum LeftButton {
+    Released,
+    /// Pressed at a position, without moving far enough to start dragging.
+    Pressed(Vec2),
+    /// Dragging, and the image has been panned to follow the cursor up to a position.
+    Dragging(Vec2),
+}
+
+/// Where bookmarks are saved when no bookmark files are given on the command line.
+const DEFAULT_BOOKMARKS_FILE: &str = "bookmarks.toml";
+
+/// The most iterations that any escaped pixel took to escape.
+fn escaped_iterations(all_pixels: &[Pixel]) -> u32 {
+    all_pixels
+        .iter()
+        .filter(|pixel| pixel.escaped == 1)
+        .map(|pixel| pixel.iteration_count)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The size of the image in batch mode, unless it's given.
+const DEFAULT_BATCH_SIZE: screen::Size = screen::Size {
+    width: 1920,
+    height: 1080,
+};
+
+/// The format that images are drawn in without a window.
+const BATCH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/// A way of running without a window, which stops once every pixel has escaped or reached the
+/// iteration limit.
+enum Batch {
+    /// Save the image to `output`.
+    Render { output: PathBuf },
+    /// Report how long it took, stopping early after `frames` frames if it's given.
+    Bench { frames: Option<u64> },
+}
+
+/// The text chunks of an image, which hold `scene`.
+fn image_text(scene: &Scene) -> Vec<(&'static str, String)> {
+    match scene::write(scene) {
+        Ok(text) => vec![(scene::PNG_KEYWORD, text)],
+        Err(err) => {
+            eprintln!("warning: the image won't have its scene: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+/// How far a [`Batch`] got before it stopped.
+struct BatchResult {
+    frames: u64,
+    elapsed: Duration,
+    unescaped_pixels: usize,
+    limited_pixels: usize,
+    total_pixels: usize,
+}
+
+impl Batch {
+    /// Whether to stop after `frames` frames, with `unescaped_pixels` pixels still being iterated.
+    fn is_finished(&self, frames: u64, unescaped_pixels: usize) -> bool {
+        match self {
+            _ if unescaped_pixels == 0 => true,
+            Batch::Bench {
+                frames: Some(max_frames),
+            } => frames >= *max_frames,
+            _ => false,
+        }
+    }
+
+    /// Report `result`, and save the image from `capture` with `text` if it's needed.
+    fn finish(
+        &self,
+        result: BatchResult,
+        capture: impl FnOnce() -> Result<Image, screenshot::Error>,
+        text: impl FnOnce() -> Vec<(&'static str, String)>,
+    ) {
+        match self {
+            Batch::Render { output } => {
+                match capture().and_then(|image| image.save(output, &text())) {
+                    Ok(()) => eprintln!(
+                        "saved {} after {} frames ({:.2?})",
+                        output.display(),
+                        result.frames,
+                        result.elapsed
+                    ),
+                    Err(err) => {
+                        eprintln!("error: {}", err);
+                        std::process::exit(1)
+                    }
+                }
+            }
+            Batch::Bench { .. } => {
+                let seconds = result.elapsed.as_secs_f64();
+                let percentage = |pixels: usize| 100.0 * pixels as f64 / result.total_pixels as f64;
+                println!(
+                    "{} frames in {:.3}s ({:.3}ms per frame, {:.1} frames per second)",
+                    result.frames,
+                    seconds,
+                    1000.0 * seconds / result.frames as f64,
+                    result.frames as f64 / seconds
+                );
+                println!(
+                    "{:.2}% escaped, {:.2}% reached the iteration limit, {:.2}% still iterating",
+                    percentage(
+                        result.total_pixels - result.limited_pixels - result.unescaped_pixels
+                    ),
+                    percentage(result.limited_pixels),
+                    percentage(result.unescaped_pixels)
+                );
+            }
+        }
+    }
+}
+
 fn main() {
-    env_logger::init();
+    // Warnings are shown without `RUST_LOG`, such as for entries skipped in bookmark files.
+    env_logger::Builder::from_env(
+        env_logger::Env::default().default_filter_or("wgpu_mandelbrot=warn"),
+    )
+    .init();
+
+    let cli = Cli::parse();
+    let (scene_args, size, supersampling, fullscreen, status_line, bookmark_paths, batch) =
+        match cli.mode {
+            None => (
+                cli.view.scene,
+                cli.view.size,
+                None,
+                cli.view.fullscreen,
+                cli.view.status_line,
+                cli.view.bookmarks,
+                None,
+            ),
+            Some(Mode::View(args)) => (
+                args.scene,
+                args.size,
+                None,
+                args.fullscreen,
+                args.status_line,
+                args.bookmarks,
+                None,
+            ),
+            Some(Mode::Render(args)) => (
+                args.scene,
+                args.size,
+                args.supersampling,
+                false,
+                false,
+                Vec::new(),
+                Some(Batch::Render {
+                    output: args.output,
+                }),
+            ),
+            Some(Mode::Bench(args)) => (
+                args.scene,
+                args.size,
+                args.supersampling,
+                false,
+                false,
+                Vec::new(),
+                Some(Batch::Bench {
+                    frames: args.frames,
+                }),
+            ),
+        };
+
+    // Rewriting a line only works in a terminal; elsewhere, every status would pile up.
+    let status_line = status_line && std::io::stderr().is_terminal();
+
+    // Options on the command line override the scene file.
+    let scene = match &scene_args.scene {
+        Some(path) => scene::load(path).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1)
+        }),
+        None => Scene::default(),
+    };
+    let size = size
+        .or(scene.size)
+        .or(batch.is_some().then_some(DEFAULT_BATCH_SIZE));
+    // Only used in batch mode, where every pixel of the image is computed this many times along
+    // each side.
+    let supersampling = supersampling.or(scene.supersampling).unwrap_or(1);
 
     ThreadPoolBuilder::new()
         .num_threads(num_cpus::get_physical())
         .build_global()
         .unwrap();
 
-    let event_loop = EventLoop::new();
-    let window = WindowBuilder::new().build(&event_loop).unwrap();
+    // There's no window to draw to in batch mode, so nothing is drawn until the image is captured.
+    let event_loop = batch.is_none().then(EventLoop::new);
+    let window = event_loop.as_ref().map(|event_loop| {
+        let mut window_builder = WindowBuilder::new().with_title("wgpu-mandelbrot");
+        if let Some(size) = size {
+            window_builder =
+                window_builder.with_inner_size(PhysicalSize::new(size.width, size.height));
+        }
+        if fullscreen {
+            window_builder = window_builder.with_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
+        window_builder.build(event_loop).unwrap()
+    });
 
     let instance = wgpu::Instance::new(wgpu::Backends::all());
 
-    let mut size = window.inner_size();
-    let surface = unsafe { instance.create_surface(&window) };
+    let surface = window
+        .as_ref()
+        .map(|window| unsafe { instance.create_surface(window) });
 
     let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
         power_preference: Default::default(),
         force_fallback_adapter: false,
-        compatible_surface: Some(&surface),
+        compatible_surface: surface.as_ref(),
     }))
     .unwrap();
 
     let (device, queue) = pollster::block_on(adapter.request_device(
         &wgpu::DeviceDescriptor {
             label: Some("device"),
-            features: wgpu::Features::empty(),
-            limits: wgpu::Limits::default(),
+            // `compute_f64.wgsl` is optional.
+            features: adapter.features() & wgpu::Features::SHADER_FLOAT64,
+            // Big images need as much as the adapter allows.
+            limits: wgpu::Limits {
+                max_texture_dimension_2d: adapter.limits().max_texture_dimension_2d,
+                max_storage_buffer_binding_size: adapter.limits().max_storage_buffer_binding_size,
+                max_buffer_size: adapter.limits().max_buffer_size,
+                ..wgpu::Limits::default()
+            },
         },
         None,
     ))
     .unwrap();
 
+    let size = match (&window, size) {
+        (Some(window), _) => window.inner_size(),
+        (None, size) => {
+            let size = size.expect("batch mode has a size");
+            // Multiplied as `u64`s, since supersampling can take the size past `u32::MAX`.
+            let width = size.width as u64 * supersampling as u64;
+            let height = size.height as u64 * supersampling as u64;
+            let limits = device.limits();
+            let pixels_size = width
+                .checked_mul(height)
+                .and_then(|pixels| pixels.checked_mul(std::mem::size_of::<Pixel>() as u64));
+            if width.max(height) > limits.max_texture_dimension_2d as u64
+                || pixels_size.is_none_or(|pixels_size| {
+                    pixels_size > limits.max_storage_buffer_binding_size as u64
+                })
+            {
+                eprintln!(
+                    "error: {}x{} pixels (after supersampling) is too big for {}",
+                    width,
+                    height,
+                    adapter.get_info().name
+                );
+                std::process::exit(1);
+            }
+            PhysicalSize::new(width as u32, height as u32)
+        }
+    };
+
     let mut surface_configuration = wgpu::SurfaceConfiguration {
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        format: surface.get_supported_formats(&adapter)[0],
+        format: surface.as_ref().map_or(BATCH_FORMAT, |surface| {
+            surface.get_supported_formats(&adapter)[0]
+        }),
         width: size.width,
         height: size.height,
         present_mode: wgpu::PresentMode::Fifo,
         alpha_mode: wgpu::CompositeAlphaMode::Auto,
     };
-    surface.configure(&device, &surface_configuration);
+    if let Some(surface) = &surface {
+        surface.configure(&device, &surface_configuration);
+    }
 
-    let compute_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-        label: Some("compute-shader"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("compute.wgsl").into()),
+    let bindings = match std::env::var_os("WGPU_MANDELBROT_BINDINGS_FILE") {
+        Some(path) => Bindings::load(path).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1)
+        }),
+        None => Bindings::default(),
+    };
+
+    // Bookmarks are loaded from every file on the command line, and saved to the first one that
+    // isn't from another program.
+    let bookmarks_path = bookmark_paths
+        .iter()
+        .find(|path| bookmarks::can_append(path))
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_BOOKMARKS_FILE));
+    let mut bookmarks: Vec<Bookmark> = Vec::new();
+    for path in &bookmark_paths {
+        bookmarks.extend(bookmarks::load(path).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1)
+        }));
+    }
+    if batch.is_none() && bookmark_paths.is_empty() && bookmarks_path.exists() {
+        bookmarks = bookmarks::load(&bookmarks_path).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1)
+        });
+    }
+    // The bookmark that was last jumped to.
+    let mut bookmark_index: Option<usize> = None;
+    // The name of the bookmark being saved, while it's typed in.
+    let mut bookmark_name: Option<String> = None;
+    // Whether a key has been pressed since `bookmark_name` started being typed in, so that the
+    // character from the key that started it isn't part of the name.
+    let mut typing_bookmark_name = false;
+
+    let custom_formula_path: Option<PathBuf> = scene
+        .formula_file
+        .clone()
+        .or_else(|| std::env::var_os("WGPU_MANDELBROT_FORMULA_FILE").map(PathBuf::from));
+    let mut custom_formula = custom_formula_path.as_ref().map(|path| {
+        CustomFormula::load(path).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1)
+        })
     });
 
     let compute_bind_group_layout_1 =
@@ -145,6 +554,94 @@ fn main() {
                     },
                     count: None,
                 },
+                // compute.wgsl#exponent
+                wgpu::BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // compute.wgsl#formula
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // compute.wgsl#coefficients
+                wgpu::BindGroupLayoutEntry {
+                    binding: 5,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // compute.wgsl#relaxation
+                wgpu::BindGroupLayoutEntry {
+                    binding: 6,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // compute.wgsl#phoenix_parameter
+                wgpu::BindGroupLayoutEntry {
+                    binding: 7,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // compute.wgsl#scale
+                wgpu::BindGroupLayoutEntry {
+                    binding: 8,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // compute.wgsl#series_approximation
+                wgpu::BindGroupLayoutEntry {
+                    binding: 9,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // compute.wgsl#double_single_scale
+                wgpu::BindGroupLayoutEntry {
+                    binding: 10,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -174,6 +671,17 @@ fn main() {
                     },
                     count: None,
                 },
+                // compute.wgsl#reference_orbit
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only: true },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -183,12 +691,27 @@ fn main() {
         push_constant_ranges: &[],
     });
 
-    let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-        label: Some("compute-pipeline"),
-        layout: Some(&compute_pipeline_layout),
-        module: &compute_shader_module,
-        entry_point: "mandelbrot",
-    });
+    let mut compute_shader_module = create_compute_shader_module(&device, custom_formula.as_ref());
+    let compute_f64_shader_module = device
+        .features()
+        .contains(wgpu::Features::SHADER_FLOAT64)
+        .then(|| {
+            device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("compute-f64-shader"),
+                source: wgpu::ShaderSource::Wgsl(compute::F64_SHADER_SOURCE.into()),
+            })
+        });
+    let device_features = device.features();
+    let engine_is_available =
+        move |engine: Engine| device_features.contains(engine.required_features());
+
+    // The engine chosen by the user, and the engine that's actually used for the current formula.
+    let mut engine = if engine_is_available(Engine::Float64) {
+        Engine::Float64
+    } else {
+        eprintln!("{} doesn't support f64 in shaders", adapter.get_info().name);
+        Engine::default()
+    };
 
     let render_shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("render-shader"),
@@ -210,6 +733,28 @@ fn main() {
                     },
                     count: None,
                 },
+                // render.wgsl#selection
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                // render.wgsl#palette
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
             ],
         });
 
@@ -269,36 +814,158 @@ fn main() {
     });
 
     let mut screen_size = screen::Size {
-        width: size.width as u32,
-        height: size.height as u32,
+        width: size.width,
+        height: size.height,
     };
     let screen_size_buffer = typed_buffer::var::Builder::new(screen_size)
         .with_label("screen-size-buffer")
         .with_usage(wgpu::BufferUsages::UNIFORM)
         .create(&device);
 
-    let mut zoom: f32 = 1.0;
-    let zoom_buffer = typed_buffer::var::Builder::new(zoom)
+    let selection_buffer = typed_buffer::var::Builder::new(NO_SELECTION)
+        .with_label("selection-buffer")
+        .with_usage(wgpu::BufferUsages::UNIFORM)
+        .create(&device);
+
+    let mut palette = scene_args.palette.or(scene.palette).unwrap_or_default();
+    // Histogram colouring is the only algorithm so far.
+    let colouring = scene.colouring.unwrap_or_default();
+    let palette_buffer = typed_buffer::var::Builder::new(palette as u32)
+        .with_label("palette-buffer")
+        .with_usage(wgpu::BufferUsages::UNIFORM)
+        .create(&device);
+
+    let mut view = scene_args
+        .apply_to(
+            scene
+                .view
+                .clone()
+                .or_else(|| parse_env_var("WGPU_MANDELBROT_VIEW"))
+                .unwrap_or_default(),
+        )
+        .unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            std::process::exit(1)
+        });
+
+    let mut history = History::new(view.clone());
+    // Set when the view changes to a step in the history, rather than to somewhere new.
+    let mut history_navigated = false;
+    // The preview to show of a step in the history, once the buffers have been reset.
+    let mut pending_preview: Option<Preview> = None;
+    // How many more frames the preview is shown for, behind the pixels that have escaped.
+    let mut preview_frames_left: Option<u32> = None;
+    // The animation to the next view, while it's running.
+    let mut transition: Option<Transition> = None;
+    // The view that the colours on screen were computed for, when they should be moved to the
+    // current view rather than cleared.
+    let mut warp_from: Option<View> = None;
+
+    let zoom_sensitivity: f64 = parse_env_var("WGPU_MANDELBROT_ZOOM_SENSITIVITY").unwrap_or(1.0);
+    if !(zoom_sensitivity.is_finite() && zoom_sensitivity > 0.0) {
+        eprintln!("error: invalid WGPU_MANDELBROT_ZOOM_SENSITIVITY: must be positive");
+        std::process::exit(1);
+    }
+    let zoom_buffer = typed_buffer::var::Builder::new(view.zoom() as f32)
         .with_label("zoom-buffer")
         .with_usage(wgpu::BufferUsages::UNIFORM)
         .create(&device);
 
-    let mut origin: Vec2 = Vec2 {
-        x: -0.74529,
-        y: 0.113075,
-    };
-    let origin_buffer = typed_buffer::var::Builder::new(origin)
-        .with_label("origin-buffer")
+    let scale_buffer = typed_buffer::var::Builder::new(Scaled::from(view.scale()))
+        .with_label("scale-buffer")
         .with_usage(wgpu::BufferUsages::UNIFORM)
         .create(&device);
 
-    let mut pixels_staging_buffer: typed_buffer::Buffer<Pixel> =
-        typed_buffer::Builder::new(screen_size.width as u64 * screen_size.height as u64)
-            .with_label("pixels_staging_buffer")
-            .with_usage(wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ)
+    let double_single_scale_buffer =
+        typed_buffer::var::Builder::new(DoubleSingle::from(view.scale()))
+            .with_label("double-single-scale-buffer")
+            .with_usage(wgpu::BufferUsages::UNIFORM)
             .create(&device);
 
-    let mut pixels_buffers = create_pixels_buffers(&device, screen_size);
+    let origin_buffer = typed_buffer::var::Builder::new(DoubleSingleComplex::from(&view.center()))
+        .with_label("origin-buffer")
+        .with_usage(wgpu::BufferUsages::UNIFORM)
+        .create(&device);
+
+    let series_approximation_buffer = typed_buffer::var::Builder::new(SeriesApproximation::NONE)
+        .with_label("series-approximation-buffer")
+        .with_usage(wgpu::BufferUsages::UNIFORM)
+        .create(&device);
+    // Pixels stop being iterated when they reach this many iterations without escaping.
+    let mut iteration_limit: u32 = scene_args
+        .iteration_limit
+        .or(scene.iteration_limit)
+        .unwrap_or(DEFAULT_ITERATION_LIMIT);
+
+    let mut exponent: f32 = scene.exponent.unwrap_or(2.0);
+    let exponent_buffer = typed_buffer::var::Builder::new(exponent)
+        .with_label("exponent-buffer")
+        .with_usage(wgpu::BufferUsages::UNIFORM)
+        .create(&device);
+
+    let mut formula = match scene_args.formula.or(scene.formula) {
+        Some(formula) => formula,
+        None if custom_formula.is_some() => Formula::Custom,
+        None => Formula::default(),
+    };
+    if formula == Formula::Custom && custom_formula.is_none() {
+        eprintln!("error: the custom formula needs WGPU_MANDELBROT_FORMULA_FILE to be set");
+        std::process::exit(1);
+    }
+
+    let mut active_engine = engine.select(formula, exponent, device_features, &view, screen_size);
+    eprintln!(
+        "using the {} engine ({})",
+        active_engine.name(),
+        active_engine.precision()
+    );
+    // Whether `active_engine` can't resolve the view.
+    let mut pixelated = !active_engine.resolves(&view, screen_size);
+    if pixelated {
+        warn_pixelated(active_engine);
+    }
+    let mut compute_pipeline = create_compute_pipeline(
+        &device,
+        &compute_pipeline_layout,
+        &compute_shader_module,
+        compute_f64_shader_module.as_ref(),
+        active_engine,
+    );
+    let formula_buffer = typed_buffer::var::Builder::new(formula as u32)
+        .with_label("formula-buffer")
+        .with_usage(wgpu::BufferUsages::UNIFORM)
+        .create(&device);
+
+    let polynomial: Polynomial = scene
+        .polynomial
+        .clone()
+        .or_else(|| parse_env_var("WGPU_MANDELBROT_POLYNOMIAL"))
+        .unwrap_or_default();
+    debug!("polynomial: {}", polynomial);
+    let coefficients_buffer = typed_buffer::Builder::from(polynomial.coefficients())
+        .with_label("coefficients-buffer")
+        .with_usage(wgpu::BufferUsages::STORAGE)
+        .create(&device);
+
+    let relaxation: Complex = scene
+        .relaxation
+        .or_else(|| parse_env_var("WGPU_MANDELBROT_RELAXATION"))
+        .unwrap_or(Complex::ONE);
+    debug!("relaxation: {}", relaxation);
+    let relaxation_buffer = typed_buffer::var::Builder::new(relaxation)
+        .with_label("relaxation-buffer")
+        .with_usage(wgpu::BufferUsages::UNIFORM)
+        .create(&device);
+
+    let phoenix_parameter: Complex = scene
+        .phoenix_parameter
+        .or_else(|| parse_env_var("WGPU_MANDELBROT_PHOENIX_PARAMETER"))
+        .unwrap_or(Complex::new(-0.5, 0.0));
+    debug!("phoenix parameter: {}", phoenix_parameter);
+    let phoenix_parameter_buffer = typed_buffer::var::Builder::new(phoenix_parameter)
+        .with_label("phoenix-parameter-buffer")
+        .with_usage(wgpu::BufferUsages::UNIFORM)
+        .create(&device);
 
     let mut compute_bind_group_1 = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("compute-bind-group-1"),
@@ -319,6 +986,46 @@ fn main() {
                 binding: 2,
                 resource: origin_buffer.binding_resource(),
             },
+            // compute.wgsl#exponent
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: exponent_buffer.binding_resource(),
+            },
+            // compute.wgsl#formula
+            wgpu::BindGroupEntry {
+                binding: 4,
+                resource: formula_buffer.binding_resource(),
+            },
+            // compute.wgsl#coefficients
+            wgpu::BindGroupEntry {
+                binding: 5,
+                resource: coefficients_buffer.binding_resource(0, None),
+            },
+            // compute.wgsl#relaxation
+            wgpu::BindGroupEntry {
+                binding: 6,
+                resource: relaxation_buffer.binding_resource(),
+            },
+            // compute.wgsl#phoenix_parameter
+            wgpu::BindGroupEntry {
+                binding: 7,
+                resource: phoenix_parameter_buffer.binding_resource(),
+            },
+            // compute.wgsl#scale
+            wgpu::BindGroupEntry {
+                binding: 8,
+                resource: scale_buffer.binding_resource(),
+            },
+            // compute.wgsl#series_approximation
+            wgpu::BindGroupEntry {
+                binding: 9,
+                resource: series_approximation_buffer.binding_resource(),
+            },
+            // compute.wgsl#double_single_scale
+            wgpu::BindGroupEntry {
+                binding: 10,
+                resource: double_single_scale_buffer.binding_resource(),
+            },
         ],
     });
 
@@ -331,33 +1038,118 @@ fn main() {
                 binding: 0,
                 resource: screen_size_buffer.binding_resource(),
             },
+            // render.wgsl#selection
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: selection_buffer.binding_resource(),
+            },
+            // render.wgsl#palette
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: palette_buffer.binding_resource(),
+            },
         ],
     });
 
+    let mut hud_visible = true;
+    let mut frame_rate = FrameRate::default();
+    // When the view last changed, and how long after that every pixel had escaped or reached the
+    // iteration limit.
+    let mut view_changed_at = Instant::now();
+    let mut converged_after: Option<Duration> = None;
+    let mut status_updated_at: Option<Instant> = None;
+
     let mut cursor_position = Vec2 { x: 0.0, y: 0.0 };
+    let mut left_button = LeftButton::Released;
+    // Where the right mouse button was pressed, while it's held to drag out a box to zoom into.
+    let mut selection_start: Option<Vec2> = None;
+    // How far the view has been panned since the last frame, in pixels.
+    let mut pan = (0, 0);
     let mut zoom_changed = false;
     let mut origin_changed = false;
+    let mut exponent_changed = false;
+    let mut formula_changed = false;
+    let mut engine_changed = false;
+    let mut size_changed = false;
+    let mut iteration_limit_lowered = false;
+    let mut screenshot_requested = false;
+    let mut scene_save_requested = false;
+    // Whether zooming in has been refused since the zoom last changed.
+    let mut zoom_limited = false;
+
+    let mut frame = Frame::new(&device, screen_size, &view);
+    frame
+        .histogram_colouring
+        .set_roots(formula.roots(&polynomial));
 
-    let mut colour_ranges_buffer: typed_buffer::Buffer<ColourRange> = typed_buffer::Builder::from(
-        std::iter::repeat(ColourRange::default())
-            .take((screen_size.width * screen_size.height) as usize)
-            .collect::<Vec<_>>()
-            .as_slice(),
-    )
-    .with_usage(wgpu::BufferUsages::STORAGE)
-    .create(&device);
-
-    let mut colour_ranges: Vec<ColourRange> = std::iter::repeat(ColourRange::default())
-        .take((screen_size.width * screen_size.height) as usize)
-        .collect();
-
-    let mut histogram_colouring = HistogramColouring::new();
+    let device = Arc::new(device);
 
-    let mut all_pixels: Vec<Pixel> = create_pixels(screen_size);
-    let mut unescaped_pixels: Vec<Pixel> = create_pixels(screen_size);
-    let mut newly_escaped_pixels: Vec<Pixel> = Vec::new();
+    if let Some(batch) = batch {
+        // Nothing changes in batch mode, so every frame carries on with the first view.
+        let start = Instant::now();
+        let mut frames: u64 = 0;
+        loop {
+            frame.compute(
+                &device,
+                &queue,
+                &compute_pipeline,
+                &compute_bind_group_1,
+                active_engine,
+                iteration_limit,
+            );
+            frame.write_colours(&queue);
+            frames += 1;
+            if batch.is_finished(frames, frame.unescaped_pixels.len()) {
+                break;
+            }
+        }
 
-    let device = Arc::new(device);
+        let render_bind_group_2 = frame.colour_ranges_bind_group(&device, &render_pipeline);
+        batch.finish(
+            BatchResult {
+                frames,
+                elapsed: start.elapsed(),
+                unescaped_pixels: frame.unescaped_pixels.len(),
+                limited_pixels: frame.limited_pixels.len(),
+                total_pixels: frame.all_pixels.len(),
+            },
+            || {
+                screenshot::capture(
+                    &device,
+                    &queue,
+                    screen_size,
+                    surface_configuration.format,
+                    &render_pipeline,
+                    &[&render_bind_group_1, &render_bind_group_2],
+                )
+                .map(|image| image.downsample(supersampling))
+            },
+            || {
+                image_text(&Scene {
+                    view: Some(view.clone()),
+                    formula: Some(formula),
+                    formula_file: custom_formula_path.clone(),
+                    exponent: Some(exponent),
+                    polynomial: Some(polynomial.clone()),
+                    relaxation: Some(relaxation),
+                    phoenix_parameter: Some(phoenix_parameter),
+                    iteration_limit: Some(iteration_limit),
+                    colouring: Some(colouring),
+                    palette: Some(palette),
+                    size: Some(screen::Size {
+                        width: screen_size.width / supersampling,
+                        height: screen_size.height / supersampling,
+                    }),
+                    supersampling: Some(supersampling),
+                })
+            },
+        );
+        return;
+    }
+    let (Some(event_loop), Some(window), Some(surface)) = (event_loop, window, surface) else {
+        unreachable!("only batch mode has no window");
+    };
+    let hud = hud::Renderer::new(&device, surface_configuration.format, &screen_size_buffer);
 
     event_loop.run(move |event, _, control_flow| {
         // To present frames in realtime, *don't* set `control_flow` to `Wait`.
@@ -369,100 +1161,579 @@ fn main() {
             }
             Event::WindowEvent { window_id, event } if window_id == window.id() => match event {
                 WindowEvent::CloseRequested => {
+                    status::finish_line();
                     *control_flow = ControlFlow::Exit;
                 }
                 WindowEvent::CursorMoved { position, .. } => {
                     cursor_position.x = position.x as f32;
                     cursor_position.y = position.y as f32;
+
+                    if let LeftButton::Pressed(start) = left_button {
+                        if (cursor_position.x - start.x).hypot(cursor_position.y - start.y)
+                            >= DRAG_THRESHOLD
+                        {
+                            left_button = LeftButton::Dragging(start);
+                        }
+                    }
+
+                    if let LeftButton::Dragging(panned_to) = &mut left_button {
+                        // Only pan by whole pixels, so that computed pixels can be moved.
+                        let x = (cursor_position.x - panned_to.x).round();
+                        let y = (cursor_position.y - panned_to.y).round();
+                        if x != 0.0 || y != 0.0 {
+                            panned_to.x += x;
+                            panned_to.y += y;
+                            pan.0 += x as i64;
+                            pan.1 += y as i64;
+
+                            view.pan(x as f64, y as f64, screen_size);
+                            trace!("view set to {}", view);
+                            origin_buffer.write(&queue, DoubleSingleComplex::from(&view.center()));
+                        }
+                    }
+
+                    if let Some(start) = selection_start {
+                        selection_buffer
+                            .write(&queue, selection(start, cursor_position, screen_size));
+                    }
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Pressed,
+                    button: winit::event::MouseButton::Right,
+                    ..
+                } => {
+                    selection_start = Some(cursor_position);
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Released,
+                    button: winit::event::MouseButton::Right,
+                    ..
+                } => {
+                    if let Some(start) = selection_start.take() {
+                        selection_buffer.write(&queue, NO_SELECTION);
+
+                        let [x0, y0, x1, y1] = selection(start, cursor_position, screen_size);
+                        if (x1 - x0).abs().max((y1 - y0).abs()) >= DRAG_THRESHOLD {
+                            let mut new_view = view.clone();
+                            let (real, imaginary) = new_view.offset_at(
+                                ((x0 + x1) / 2.0) as f64,
+                                ((y0 + y1) / 2.0) as f64,
+                                screen_size,
+                            );
+                            new_view.translate(real, imaginary);
+                            new_view.set_zoom(
+                                (view.zoom() * screen_size.width as f64 / (x1 - x0).abs() as f64)
+                                    .min(f64::MAX),
+                            );
+
+                            let new_engine = engine.select(
+                                formula,
+                                exponent,
+                                device_features,
+                                &new_view,
+                                screen_size,
+                            );
+                            if new_engine.resolves(&new_view, screen_size) {
+                                view = new_view;
+                                debug!("view set to {}", view);
+                                zoom_changed = true;
+                                origin_changed = true;
+                                write_view(
+                                    &queue,
+                                    &view,
+                                    &zoom_buffer,
+                                    &scale_buffer,
+                                    &double_single_scale_buffer,
+                                    &origin_buffer,
+                                );
+                            } else {
+                                warn_zoom_limited(new_engine);
+                            }
+                        }
+                    }
                 }
                 WindowEvent::MouseInput {
-                    state: winit::event::ElementState::Pressed,
+                    state: ElementState::Pressed,
                     button: winit::event::MouseButton::Left,
                     ..
                 } => {
                     debug!("mouse pressed at {:?}", cursor_position);
+                    left_button = LeftButton::Pressed(cursor_position);
+                }
+                WindowEvent::MouseInput {
+                    state: ElementState::Released,
+                    button: winit::event::MouseButton::Left,
+                    ..
+                } => {
+                    if let LeftButton::Pressed(position) = left_button {
+                        let (real, imaginary) =
+                            view.offset_at(position.x as f64, position.y as f64, screen_size);
+                        view.translate(real, imaginary);
+                        debug!("view set to {}", view);
+                        origin_changed = true;
+                        origin_buffer.write(&queue, DoubleSingleComplex::from(&view.center()));
+                    }
+                    left_button = LeftButton::Released;
+                }
+                WindowEvent::MouseWheel { .. } | WindowEvent::TouchpadMagnify { .. } => {
+                    // How much one line of scrolling zooms by.
+                    const ZOOM_PER_LINE: f64 = 1.1;
+                    // Roughly the height of the line of text that a `LineDelta` of `1` scrolls by.
+                    const PIXELS_PER_LINE: f64 = 20.0;
+
+                    // Zoom by multiplying, so that no amount of scrolling can reach zero.
+                    let factor = match event {
+                        WindowEvent::MouseWheel { delta, .. } => {
+                            let lines = match delta {
+                                winit::event::MouseScrollDelta::LineDelta(_, delta) => delta as f64,
+                                winit::event::MouseScrollDelta::PixelDelta(position) => {
+                                    position.y / PIXELS_PER_LINE
+                                }
+                            };
+                            ZOOM_PER_LINE.powf(lines)
+                        }
+                        // `delta` is the change in magnification, where `0` is none.
+                        WindowEvent::TouchpadMagnify { delta, .. } => delta.exp(),
+                        _ => unreachable!(),
+                    };
 
-                    /*
-                    when `zoom = 1.0`, we're viewing (-2, -2) to (2, 2).
-
-                    (0, 0) corresponds to (size.width / 2, size.height / 2)
+                    // Keep the point under the cursor in place.
+                    let zoom = view.zoom();
+                    let mut new_view = view.clone();
+                    new_view.zoom_at(
+                        (zoom * factor.powf(zoom_sensitivity)).clamp(f64::MIN_POSITIVE, f64::MAX),
+                        cursor_position.x as f64,
+                        cursor_position.y as f64,
+                        screen_size,
+                    );
 
-                    A click at (cursor_x, cursor_y) corresponds to (4 * cursor_x / size.width - 2, 4 * cursor_y / size.height - 2)
-                     */
+                    // Zooming in past the precision of every engine would only make the blocks
+                    // bigger. Zooming out is always allowed.
+                    let new_engine =
+                        engine.select(formula, exponent, device_features, &new_view, screen_size);
+                    if new_view.zoom() > zoom && !new_engine.resolves(&new_view, screen_size) {
+                        if !zoom_limited {
+                            warn_zoom_limited(new_engine);
+                            zoom_limited = true;
+                        }
+                    } else {
+                        zoom_limited = false;
+                        view = new_view;
+                        debug!("view set to {}", view);
+                        zoom_changed = true;
+                        origin_changed = true;
+                        write_view(
+                            &queue,
+                            &view,
+                            &zoom_buffer,
+                            &scale_buffer,
+                            &double_single_scale_buffer,
+                            &origin_buffer,
+                        );
+                    }
+                }
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(keycode),
+                            ..
+                        },
+                    ..
+                } => {
+                    if let Some(name) = &mut bookmark_name {
+                        typing_bookmark_name = true;
+                        match keycode {
+                            VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+                                let name = match name.trim() {
+                                    "" => format!("bookmark {}", bookmarks.len() + 1),
+                                    name => name.to_string(),
+                                };
+                                eprintln!();
+                                let bookmark = Bookmark {
+                                    name,
+                                    view: view.clone(),
+                                    formula: Some(formula),
+                                    exponent: Some(exponent),
+                                    iteration_limit: Some(iteration_limit),
+                                    palette: Some(palette),
+                                };
+                                match bookmarks::append(&bookmarks_path, &bookmark) {
+                                    Ok(()) => {
+                                        eprintln!(
+                                            "saved bookmark {:?} to {}",
+                                            bookmark.name,
+                                            bookmarks_path.display()
+                                        );
+                                        bookmarks.push(bookmark);
+                                    }
+                                    Err(err) => eprintln!("error: {}", err),
+                                }
+                                bookmark_name = None;
+                            }
+                            VirtualKeyCode::Back if name.pop().is_some() => {
+                                eprint!("\u{8} \u{8}");
+                            }
+                            VirtualKeyCode::Escape => {
+                                eprintln!();
+                                eprintln!("not saving the bookmark");
+                                bookmark_name = None;
+                            }
+                            _ => {}
+                        }
+                        // Keys type the name, rather than running commands.
+                        return;
+                    }
 
-                    let zoom_inv = 2.0 / zoom;
-                    origin = Vec2 {
-                        x: origin.x
-                            + (2.0 * zoom_inv * cursor_position.x / (size.width as f32) - zoom_inv),
-                        y: origin.y
-                            + (2.0 * zoom_inv * cursor_position.y / (size.height as f32)
-                                - zoom_inv),
+                    let Some(command) = bindings.command(keycode) else {
+                        return;
                     };
-                    debug!("origin set to {:?}", origin);
-                    origin_changed = true;
-                    origin_buffer.write(&queue, origin);
-                }
-                WindowEvent::MouseWheel { delta, .. } => {
-                    zoom += zoom
-                        * 0.1
-                        * match delta {
-                            winit::event::MouseScrollDelta::LineDelta(_, delta) => delta,
-                            winit::event::MouseScrollDelta::PixelDelta(_) => {
-                                panic!("expected LineDelta, got PixelDelta")
+                    debug!("{:?} pressed, running {:?}", keycode, command);
+
+                    match command {
+                        Command::PanLeft
+                        | Command::PanRight
+                        | Command::PanUp
+                        | Command::PanDown => {
+                            // Only pan by whole pixels, so that computed pixels can be moved.
+                            let x = (screen_size.width as f64 * KEY_PAN_FRACTION).round() as i64;
+                            let y = (screen_size.height as f64 * KEY_PAN_FRACTION).round() as i64;
+                            // Panning left moves the contents of the screen right.
+                            let (x, y) = match command {
+                                Command::PanLeft => (x, 0),
+                                Command::PanRight => (-x, 0),
+                                Command::PanUp => (0, y),
+                                _ => (0, -y),
+                            };
+                            pan.0 += x;
+                            pan.1 += y;
+
+                            view.pan(x as f64, y as f64, screen_size);
+                            debug!("view set to {}", view);
+                            origin_buffer.write(&queue, DoubleSingleComplex::from(&view.center()));
+                        }
+                        Command::ZoomIn | Command::ZoomOut => {
+                            let factor = if command == Command::ZoomIn {
+                                KEY_ZOOM_FACTOR
+                            } else {
+                                KEY_ZOOM_FACTOR.recip()
+                            };
+                            let mut new_view = view.clone();
+                            new_view.set_zoom(
+                                (view.zoom() * factor).clamp(f64::MIN_POSITIVE, f64::MAX),
+                            );
+
+                            // As with the scroll wheel, zooming in is refused once no engine can
+                            // resolve the view.
+                            let new_engine = engine.select(
+                                formula,
+                                exponent,
+                                device_features,
+                                &new_view,
+                                screen_size,
+                            );
+                            if command == Command::ZoomIn
+                                && !new_engine.resolves(&new_view, screen_size)
+                            {
+                                warn_zoom_limited(new_engine);
+                            } else {
+                                view = new_view;
+                                debug!("view set to {}", view);
+                                zoom_changed = true;
+                                write_view(
+                                    &queue,
+                                    &view,
+                                    &zoom_buffer,
+                                    &scale_buffer,
+                                    &double_single_scale_buffer,
+                                    &origin_buffer,
+                                );
+                            }
+                        }
+                        Command::Reset => {
+                            view = View::default();
+                            debug!("view set to {}", view);
+                            zoom_changed = true;
+                            origin_changed = true;
+                            write_view(
+                                &queue,
+                                &view,
+                                &zoom_buffer,
+                                &scale_buffer,
+                                &double_single_scale_buffer,
+                                &origin_buffer,
+                            );
+                        }
+                        Command::Undo | Command::Redo | Command::Home => {
+                            let preview = Preview::capture(
+                                screen_size,
+                                &frame.colour_ranges,
+                                &frame.all_pixels,
+                            );
+                            let entry = match command {
+                                Command::Undo => history.undo(preview),
+                                Command::Redo => history.redo(preview),
+                                _ => Some(history.home(preview)),
+                            };
+
+                            if let Some(Entry {
+                                view: new_view,
+                                preview,
+                            }) = entry
+                            {
+                                debug!("animating to {}", new_view);
+                                transition = Some(Transition::new(
+                                    view.clone(),
+                                    new_view,
+                                    escaped_iterations(&frame.all_pixels),
+                                ));
+                                pending_preview = preview;
+                            }
+                        }
+                        Command::SaveBookmark => {
+                            eprint!("bookmark name (Enter to save, Escape to cancel): ");
+                            bookmark_name = Some(String::new());
+                            typing_bookmark_name = false;
+                        }
+                        Command::ListBookmarks => {
+                            if bookmarks.is_empty() {
+                                println!("no bookmarks");
+                            }
+                            for (index, bookmark) in bookmarks.iter().enumerate() {
+                                let marker = if bookmark_index == Some(index) {
+                                    '*'
+                                } else {
+                                    ' '
+                                };
+                                println!(
+                                    "{} {}. {}: {}",
+                                    marker,
+                                    index + 1,
+                                    bookmark.name,
+                                    bookmark.view
+                                );
+                            }
+                        }
+                        Command::NextBookmark | Command::PreviousBookmark => {
+                            if bookmarks.is_empty() {
+                                eprintln!("no bookmarks");
+                                return;
+                            }
+                            let index = match (command, bookmark_index) {
+                                (Command::NextBookmark, Some(index)) => {
+                                    (index + 1) % bookmarks.len()
+                                }
+                                (Command::NextBookmark, None) => 0,
+                                (_, Some(index)) => (index + bookmarks.len() - 1) % bookmarks.len(),
+                                (_, None) => bookmarks.len() - 1,
+                            };
+                            bookmark_index = Some(index);
+                            let bookmark = &bookmarks[index];
+                            eprintln!("jumping to bookmark {}. {}", index + 1, bookmark.name);
+
+                            match bookmark.formula {
+                                Some(Formula::Custom) if custom_formula.is_none() => {
+                                    eprintln!("warning: no custom formula is loaded");
+                                }
+                                Some(new_formula) if new_formula != formula => {
+                                    formula = new_formula;
+                                    debug!("formula set to {}", formula.name());
+                                    frame
+                                        .histogram_colouring
+                                        .set_roots(formula.roots(&polynomial));
+                                    formula_changed = true;
+                                    formula_buffer.write(&queue, formula as u32);
+                                }
+                                _ => {}
+                            }
+                            if let Some(new_exponent) = bookmark
+                                .exponent
+                                .filter(|new_exponent| *new_exponent != exponent)
+                            {
+                                exponent = new_exponent;
+                                debug!("exponent set to {}", exponent);
+                                exponent_changed = true;
+                                exponent_buffer.write(&queue, exponent);
+                            }
+                            if let Some(new_palette) = bookmark.palette {
+                                palette = new_palette;
+                                debug!("palette set to {}", palette.name());
+                                palette_buffer.write(&queue, palette as u32);
+                            }
+                            if let Some(new_iteration_limit) = bookmark.iteration_limit {
+                                if new_iteration_limit < iteration_limit {
+                                    iteration_limit_lowered = true;
+                                }
+                                iteration_limit = new_iteration_limit;
+                                debug!("iteration limit set to {}", iteration_limit);
+                                frame.set_iteration_limit(iteration_limit);
+                            }
+
+                            let preview = Preview::capture(
+                                screen_size,
+                                &frame.colour_ranges,
+                                &frame.all_pixels,
+                            );
+                            history.push(bookmark.view.clone(), preview);
+                            transition = Some(Transition::new(
+                                view.clone(),
+                                bookmark.view.clone(),
+                                escaped_iterations(&frame.all_pixels),
+                            ));
+                            pending_preview = None;
+                        }
+                        Command::IncreaseIterationLimit => {
+                            iteration_limit = iteration_limit.saturating_mul(2);
+                            eprintln!("iteration limit set to {}", iteration_limit);
+                            // Carry on from where the limited pixels stopped.
+                            frame.set_iteration_limit(iteration_limit);
+                        }
+                        Command::DecreaseIterationLimit => {
+                            iteration_limit = (iteration_limit / 2).max(1);
+                            eprintln!("iteration limit set to {}", iteration_limit);
+                            // Pixels that escaped after the new limit have to be uncoloured.
+                            iteration_limit_lowered = true;
+                        }
+                        Command::IncreaseExponent
+                        | Command::DecreaseExponent
+                        | Command::IncreaseExponentFine
+                        | Command::DecreaseExponentFine => {
+                            let exponent_delta = match command {
+                                Command::IncreaseExponent => 1.0,
+                                Command::DecreaseExponent => -1.0,
+                                Command::IncreaseExponentFine => 0.1,
+                                _ => -0.1,
+                            };
+                            // Round to the nearest tenth so that repeated fractional steps can land
+                            // back on an integer exponent.
+                            exponent = ((exponent + exponent_delta) * 10.0).round() / 10.0;
+                            debug!("exponent set to {}", exponent);
+                            exponent_changed = true;
+                            exponent_buffer.write(&queue, exponent);
+                        }
+                        Command::NextFormula => {
+                            formula = formula.next();
+                            if formula == Formula::Custom && custom_formula.is_none() {
+                                formula = formula.next();
+                            }
+                            debug!("formula set to {}", formula.name());
+                            frame
+                                .histogram_colouring
+                                .set_roots(formula.roots(&polynomial));
+                            formula_changed = true;
+                            formula_buffer.write(&queue, formula as u32);
+                        }
+                        Command::ReloadFormula => {
+                            if let Some(path) = &custom_formula_path {
+                                match CustomFormula::load(path) {
+                                    Ok(new_custom_formula) => {
+                                        debug!("reloaded {}", path.display());
+                                        compute_shader_module = create_compute_shader_module(
+                                            &device,
+                                            Some(&new_custom_formula),
+                                        );
+                                        compute_pipeline = create_compute_pipeline(
+                                            &device,
+                                            &compute_pipeline_layout,
+                                            &compute_shader_module,
+                                            compute_f64_shader_module.as_ref(),
+                                            active_engine,
+                                        );
+                                        custom_formula = Some(new_custom_formula);
+                                        formula_changed |= formula == Formula::Custom;
+                                    }
+                                    // Keep the previous formula, so that mistakes can be fixed
+                                    // without restarting.
+                                    Err(err) => eprintln!("error: {}", err),
+                                }
+                            }
+                        }
+                        Command::NextEngine => {
+                            engine = engine.next();
+                            while !engine_is_available(engine) {
+                                engine = engine.next();
                             }
-                        };
-                    zoom_changed = true;
-                    zoom_buffer.write(&queue, zoom);
+                            debug!("engine set to {}", engine.name());
+                            engine_changed = true;
+                        }
+                        Command::NextPalette => {
+                            palette = palette.next();
+                            debug!("palette set to {}", palette.name());
+                            palette_buffer.write(&queue, palette as u32);
+                        }
+                        Command::PrintView => {
+                            // In the format of `WGPU_MANDELBROT_VIEW`.
+                            println!("{}", view);
+                        }
+                        Command::Screenshot => {
+                            // Taken after the next frame is drawn.
+                            screenshot_requested = true;
+                        }
+                        Command::ExportLocation => {
+                            let seconds = SystemTime::now()
+                                .duration_since(SystemTime::UNIX_EPOCH)
+                                .map_or(0, |duration| duration.as_secs());
+                            let name = format!("wgpu-mandelbrot-{}", seconds);
+                            let bookmark = Bookmark {
+                                name: name.clone(),
+                                view: view.clone(),
+                                formula: Some(formula),
+                                exponent: Some(exponent),
+                                iteration_limit: Some(iteration_limit),
+                                palette: Some(palette),
+                            };
+
+                            // Either format can be missing if it can't show the formula.
+                            let exports = [
+                                ("kfr", kfr::write(&bookmark).map_err(|err| err.to_string())),
+                                ("par", par::write(&bookmark).map_err(|err| err.to_string())),
+                            ];
+                            for (extension, result) in exports {
+                                let path = PathBuf::from(format!("{}.{}", name, extension));
+                                match result.and_then(|text| {
+                                    std::fs::write(&path, text).map_err(|err| {
+                                        format!("failed to write {}: {}", path.display(), err)
+                                    })
+                                }) {
+                                    Ok(()) => eprintln!("exported view to {}", path.display()),
+                                    Err(err) => {
+                                        eprintln!("couldn't export .{}: {}", extension, err)
+                                    }
+                                }
+                            }
+                        }
+                        Command::SaveScene => {
+                            // Saved after the next frame is drawn, along with screenshots.
+                            scene_save_requested = true;
+                        }
+                        Command::ToggleHud => {
+                            hud_visible = !hud_visible;
+                        }
+                    }
+                }
+                WindowEvent::ReceivedCharacter(character) => {
+                    if let Some(name) = &mut bookmark_name {
+                        if typing_bookmark_name && !character.is_control() {
+                            name.push(character);
+                            eprint!("{}", character);
+                        }
+                    }
                 }
                 WindowEvent::Resized(new_size) => {
                     debug!("resizing to {:?}", new_size);
-                    size = new_size;
+                    size_changed = true;
                     screen_size = screen::Size {
-                        width: size.width as u32,
-                        height: size.height as u32,
+                        width: new_size.width,
+                        height: new_size.height,
                     };
 
-                    surface_configuration.width = size.width;
-                    surface_configuration.height = size.height;
+                    surface_configuration.width = new_size.width;
+                    surface_configuration.height = new_size.height;
 
                     surface.configure(&device, &surface_configuration);
 
-                    colour_ranges.clear();
-                    colour_ranges.extend(
-                        std::iter::repeat(ColourRange::default())
-                            .take((screen_size.width * screen_size.height) as usize),
-                    );
-                    histogram_colouring.reset();
-
                     screen_size_buffer.write(&queue, screen_size);
-
-                    pixels_staging_buffer = typed_buffer::Builder::new(
-                        screen_size.width as u64 * screen_size.height as u64,
-                    )
-                    .with_label("pixels_staging_buffer")
-                    .with_usage(wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ)
-                    .create(&device);
-
-                    std::mem::replace(
-                        &mut pixels_buffers,
-                        create_pixels_buffers(&device, screen_size),
-                    )
-                    .destroy();
-                    all_pixels = create_pixels(screen_size);
-                    unescaped_pixels = create_pixels(screen_size);
-
-                    std::mem::replace(
-                        &mut colour_ranges_buffer,
-                        typed_buffer::Builder::from(
-                            std::iter::repeat(ColourRange::default())
-                                .take((screen_size.width * screen_size.height) as usize)
-                                .collect::<Vec<_>>()
-                                .as_slice(),
-                        )
-                        .with_usage(wgpu::BufferUsages::STORAGE)
-                        .create(&device),
-                    )
-                    .destroy();
+                    frame.resize(&device, screen_size);
 
                     compute_bind_group_1 = device.create_bind_group(&wgpu::BindGroupDescriptor {
                         label: Some("compute-bind-group"),
@@ -483,6 +1754,46 @@ fn main() {
                                 binding: 2,
                                 resource: origin_buffer.binding_resource(),
                             },
+                            // compute.wgsl#exponent
+                            wgpu::BindGroupEntry {
+                                binding: 3,
+                                resource: exponent_buffer.binding_resource(),
+                            },
+                            // compute.wgsl#formula
+                            wgpu::BindGroupEntry {
+                                binding: 4,
+                                resource: formula_buffer.binding_resource(),
+                            },
+                            // compute.wgsl#coefficients
+                            wgpu::BindGroupEntry {
+                                binding: 5,
+                                resource: coefficients_buffer.binding_resource(0, None),
+                            },
+                            // compute.wgsl#relaxation
+                            wgpu::BindGroupEntry {
+                                binding: 6,
+                                resource: relaxation_buffer.binding_resource(),
+                            },
+                            // compute.wgsl#phoenix_parameter
+                            wgpu::BindGroupEntry {
+                                binding: 7,
+                                resource: phoenix_parameter_buffer.binding_resource(),
+                            },
+                            // compute.wgsl#scale
+                            wgpu::BindGroupEntry {
+                                binding: 8,
+                                resource: scale_buffer.binding_resource(),
+                            },
+                            // compute.wgsl#series_approximation
+                            wgpu::BindGroupEntry {
+                                binding: 9,
+                                resource: series_approximation_buffer.binding_resource(),
+                            },
+                            // compute.wgsl#double_single_scale
+                            wgpu::BindGroupEntry {
+                                binding: 10,
+                                resource: double_single_scale_buffer.binding_resource(),
+                            },
                         ],
                     });
 
@@ -495,6 +1806,16 @@ fn main() {
                                 binding: 0,
                                 resource: screen_size_buffer.binding_resource(),
                             },
+                            // render.wgsl#selection
+                            wgpu::BindGroupEntry {
+                                binding: 1,
+                                resource: selection_buffer.binding_resource(),
+                            },
+                            // render.wgsl#palette
+                            wgpu::BindGroupEntry {
+                                binding: 2,
+                                resource: palette_buffer.binding_resource(),
+                            },
                         ],
                     });
 
@@ -503,193 +1824,226 @@ fn main() {
                 _ => {}
             },
             Event::RedrawRequested(window_id) if window_id == window.id() => {
-                debug_assert!(
-                    unescaped_pixels.len()
-                        <= screen_size.width as usize * screen_size.height as usize
-                );
-                if cfg!(debug_assertions) {
-                    for pixel in unescaped_pixels.iter() {
-                        debug_assert!(pixel.escaped < 2);
+                if let Some(active_transition) = &mut transition {
+                    if view != *active_transition.current() {
+                        debug!("transition interrupted");
+                        transition = None;
+                        pending_preview = None;
+                    } else {
+                        // Driven by the clock, rather than by input.
+                        let now = Instant::now();
+                        warp_from = Some(view.clone());
+                        view = active_transition.view_at(now);
+                        trace!("view set to {}", view);
+                        history_navigated = true;
+                        zoom_changed = true;
+                        origin_changed = true;
+                        write_view(
+                            &queue,
+                            &view,
+                            &zoom_buffer,
+                            &scale_buffer,
+                            &double_single_scale_buffer,
+                            &origin_buffer,
+                        );
+                        // The colours that are carried along are only cleared once it's over.
+                        preview_frames_left = None;
+                        if active_transition.is_finished(now) {
+                            debug!("view set to {}", view);
+                            preview_frames_left = Some(active_transition.iterations() + 1);
+                            transition = None;
+                        }
+                    }
+                }
+
+                if engine_changed
+                    || exponent_changed
+                    || formula_changed
+                    || zoom_changed
+                    || origin_changed
+                    || size_changed
+                    || pan != (0, 0)
+                {
+                    let new_active_engine =
+                        engine.select(formula, exponent, device_features, &view, screen_size);
+                    let new_pixelated = !new_active_engine.resolves(&view, screen_size);
+                    if new_pixelated && (!pixelated || new_active_engine != active_engine) {
+                        warn_pixelated(new_active_engine);
                     }
+                    pixelated = new_pixelated;
+
+                    if new_active_engine != active_engine {
+                        active_engine = new_active_engine;
+                        eprintln!(
+                            "using the {} engine ({})",
+                            active_engine.name(),
+                            active_engine.precision()
+                        );
+                        compute_pipeline = create_compute_pipeline(
+                            &device,
+                            &compute_pipeline_layout,
+                            &compute_shader_module,
+                            compute_f64_shader_module.as_ref(),
+                            active_engine,
+                        );
+                    } else {
+                        // Nothing to reset.
+                        engine_changed = false;
+                    }
+                }
+
+                if (zoom_changed || origin_changed || pan != (0, 0)) && !history_navigated {
+                    // `colour_ranges` still shows the view that's being left.
+                    history.visit(view.clone(), || {
+                        Preview::capture(screen_size, &frame.colour_ranges, &frame.all_pixels)
+                    });
+                }
+                history_navigated = false;
+                if exponent_changed || formula_changed || iteration_limit_lowered {
+                    history.clear_previews();
                 }
 
-                let reset_buffers = zoom_changed || origin_changed;
+                let reset_buffers = zoom_changed
+                    || origin_changed
+                    || exponent_changed
+                    || formula_changed
+                    || engine_changed
+                    || size_changed
+                    || iteration_limit_lowered;
+                // Panning only needs the pixels that come into view to be computed.
+                let panned = !reset_buffers && pan != (0, 0);
+                size_changed = false;
                 zoom_changed = false;
                 origin_changed = false;
+                exponent_changed = false;
+                formula_changed = false;
+                engine_changed = false;
+                iteration_limit_lowered = false;
+
+                // The reference orbit is at the center of the screen.
+                if reset_buffers || (panned && active_engine == Engine::Perturbation) {
+                    let series_approximation =
+                        frame.move_reference_orbit(&device, &view, active_engine);
+                    series_approximation_buffer.write(&queue, series_approximation);
+                }
 
                 if reset_buffers {
-                    colour_ranges.clear();
-                    colour_ranges.extend(
-                        std::iter::repeat(ColourRange::default())
-                            .take((screen_size.width * screen_size.height) as usize),
-                    );
-                    histogram_colouring.reset();
+                    match warp_from.take() {
+                        // Carry the colours along until the pixels are computed again.
+                        Some(from) => {
+                            transition::warp(&from, &view, screen_size, &mut frame.colour_ranges);
+                        }
+                        _ => {
+                            frame.clear_colours();
+                            preview_frames_left = None;
+                        }
+                    }
+                    frame.reset(&queue);
 
-                    let pixels = create_pixels(screen_size);
-                    pixels_buffers.input.write(&queue, &pixels);
-                    pixels_buffers.output.write(&queue, &pixels);
-                    all_pixels = pixels.clone();
-                    unescaped_pixels = pixels;
+                    // Shown once the transition to its view is over.
+                    if let Some(preview) = pending_preview.take_if(|_| transition.is_none()) {
+                        preview.draw(screen_size, &mut frame.colour_ranges);
+                        preview_frames_left = Some(preview.iterations() + 1);
+                    }
+                } else if panned {
+                    frame.pan(pan.0, pan.1, active_engine, iteration_limit);
+                    preview_frames_left = None;
+                }
+                if reset_buffers || panned {
+                    view_changed_at = Instant::now();
                 }
+                pan = (0, 0);
+
+                frame.compute(
+                    &device,
+                    &queue,
+                    &compute_pipeline,
+                    &compute_bind_group_1,
+                    active_engine,
+                    iteration_limit,
+                );
+                preview_frames_left = match preview_frames_left {
+                    Some(0) => {
+                        frame.clear_preview();
+                        None
+                    }
+                    frames_left => frames_left.map(|frames_left| frames_left - 1),
+                };
+                frame.write_colours(&queue);
 
                 let surface_texture = surface.get_current_texture().unwrap();
                 let surface_texture_view = surface_texture
                     .texture
                     .create_view(&wgpu::TextureViewDescriptor::default());
+                let render_bind_group_2 = frame.colour_ranges_bind_group(&device, &render_pipeline);
+
+                let now = Instant::now();
+                let mut progress = Progress {
+                    view: &view,
+                    escaped_pixels: frame.all_pixels.len()
+                        - frame.unescaped_pixels.len()
+                        - frame.limited_pixels.len(),
+                    limited_pixels: frame.limited_pixels.len(),
+                    total_pixels: frame.all_pixels.len(),
+                    elapsed: now - view_changed_at,
+                };
+                let newly_converged = progress.is_converged() && converged_after.is_none();
+                if !progress.is_converged() {
+                    converged_after = None;
+                } else if newly_converged {
+                    converged_after = Some(progress.elapsed);
+                }
+                progress.elapsed = converged_after.unwrap_or(progress.elapsed);
 
-                let compute_bind_group_2 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    label: Some("compute-bind-group-2"),
-                    layout: &compute_bind_group_layout_2,
-                    entries: &[
-                        // compute.wgsl#input
-                        wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: pixels_buffers.input.binding_resource(0, None),
-                        },
-                        // compute.wgsl#output
-                        wgpu::BindGroupEntry {
-                            binding: 1,
-                            resource: pixels_buffers.output.binding_resource(0, None),
-                        },
-                    ],
-                });
-
-                let render_bind_group_2 = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    label: Some("render-bind-group-2"),
-                    layout: &render_pipeline.get_bind_group_layout(1),
-                    entries: &[
-                        // render.wgsl#colour_ranges
-                        wgpu::BindGroupEntry {
-                            binding: 0,
-                            resource: colour_ranges_buffer.binding_resource(0, None),
-                        },
-                    ],
-                });
-
-                let compute_command_buffer = command_buffer::create(
-                    &device,
-                    &wgpu::CommandEncoderDescriptor::default(),
-                    |command_encoder| {
-                        pixels_buffers.input.write(&queue, &unescaped_pixels);
-
-                        command_encoder.push_debug_group("compute-pass");
-                        command_encoder.with_compute_pass(
-                            &wgpu::ComputePassDescriptor {
-                                label: Some("compute-pass"),
-                            },
-                            |compute_pass| {
-                                compute_pass.set_pipeline(&compute_pipeline);
-
-                                compute_pass.set_bind_group(0, &compute_bind_group_1, &[]);
-                                compute_pass.set_bind_group(1, &compute_bind_group_2, &[]);
-
-                                compute_pass.insert_debug_marker("mandelbrot");
-
-                                let total_work = unescaped_pixels.len();
-
-                                let (x, y, z) = compute::mandelbrot_dispatch_size(total_work);
-
-                                compute_pass.dispatch_workgroups(x, y, z);
-                            },
-                        );
-                        command_encoder.pop_debug_group();
-
-                        typed_buffer::copy_buffer_to_buffer(
-                            command_encoder,
-                            &pixels_buffers.output,
-                            0,
-                            &pixels_staging_buffer,
-                            0,
-                            unescaped_pixels.len().try_into().unwrap(),
-                        );
-                    },
-                );
-
-                queue.submit([compute_command_buffer]);
-
-                let pixels_staging_buffer_slice = pixels_staging_buffer.slice(..);
-
+                // Nothing changes while the view is converged.
+                if newly_converged
+                    || (!progress.is_converged()
+                        && status_updated_at
+                            .is_none_or(|updated_at| now - updated_at >= STATUS_INTERVAL))
                 {
-                    trace!("waiting for staging buffer");
-                    let mapped = Arc::new((Mutex::new(true), Condvar::new()));
-
-                    pixels_staging_buffer_slice.map_async(wgpu::MapMode::Read, {
-                        let mapped = mapped.clone();
-                        move |map_result| {
-                            debug!("map_async callback called");
-                            map_result.unwrap_or_else(|err| panic!("buffer async error: {}", err));
-                            let mut guard = mapped.0.lock().unwrap();
-                            *guard = false;
-                            mapped.1.notify_all();
-                        }
-                    });
-
-                    {
-                        let device = device.clone();
-                        std::thread::spawn(move || while !device.poll(wgpu::Maintain::Poll) {});
+                    status_updated_at = Some(now);
+                    window.set_title(&progress.title());
+                    // The status line would be written over a bookmark name being typed.
+                    if status_line && bookmark_name.is_none() {
+                        status::show_line(&progress);
                     }
-
-                    debug!("waiting for condition");
-                    let _guard = mapped
-                        .1
-                        .wait_while(mapped.0.lock().unwrap(), |pending| *pending)
-                        .unwrap();
-                    debug!("staging buffer mapped");
                 }
-
-                {
-                    let pixels_staging_buffer_view: typed_buffer::View<Pixel> =
-                        pixels_staging_buffer_slice.get_mapped_range();
-
-                    let unescaped_pixels_len = unescaped_pixels.len();
-                    unescaped_pixels.clear();
-                    newly_escaped_pixels.clear();
-
-                    pixels_staging_buffer_view
-                        .iter()
-                        /*
-                        This caused a bug for me: even though I copy `unescaped_pixels.len()`
-                        worth of data into the staging buffer, the buffer is still the size
-                        of the screen.
-                        Without the `take`, I was iterating over every pixel in the buffer.
-                        Everything after `unescaped_pixels.len()` in the buffer is effectively
-                        garbage (leftover from previous runs), but I was including it in the
-                        `newly_escaped` array anyway.
-                        */
-                        .take(unescaped_pixels_len)
-                        .for_each(|pixel| {
-                            let pixel = *pixel;
-
-                            debug_assert!(pixel.x < screen_size.width);
-                            debug_assert!(pixel.y < screen_size.height);
-                            debug_assert!(pixel.escaped < 2);
-
-                            if pixel.escaped == 1 {
-                                all_pixels[pixel.y as usize * screen_size.width as usize
-                                    + pixel.x as usize] = pixel;
-                                newly_escaped_pixels.push(pixel);
-                            } else {
-                                unescaped_pixels.push(pixel);
-                            }
-                        });
+                if newly_converged && !status_line {
+                    eprintln!(
+                        "converged after {:.2?} ({:.1}% escaped)",
+                        progress.elapsed,
+                        100.0 * progress.escaped_fraction()
+                    );
                 }
 
-                pixels_staging_buffer.buffer().unmap();
-
-                histogram_colouring.update_colours(
-                    screen_size,
-                    &all_pixels,
-                    &newly_escaped_pixels,
-                    &mut colour_ranges,
-                );
-                debug_assert!(
-                    colour_ranges.len() == screen_size.width as usize * screen_size.height as usize,
-                    "colour_ranges.len() == {}, expected {}",
-                    colour_ranges.len(),
-                    screen_size.width * screen_size.height,
-                );
-
-                colour_ranges_buffer.write(&queue, &colour_ranges);
+                frame_rate.tick(Instant::now());
+                if hud_visible {
+                    let (real, imaginary) = view.offset_at(
+                        cursor_position.x as f64,
+                        cursor_position.y as f64,
+                        screen_size,
+                    );
+                    let mut cursor = view.clone();
+                    cursor.translate(real, imaginary);
+                    let text = hud::Text::new(&[
+                        format!("cursor re {}", cursor.real()),
+                        format!("cursor im {}", cursor.imaginary()),
+                        format!("zoom {:e}", view.zoom()),
+                        format!("iteration limit {}", iteration_limit),
+                        format!(
+                            "iterating {} of {} pixels",
+                            frame.unescaped_pixels.len(),
+                            frame.all_pixels.len()
+                        ),
+                        format!("{} fps", frame_rate.per_second()),
+                        format!(
+                            "{} engine ({})",
+                            active_engine.name(),
+                            active_engine.precision()
+                        ),
+                    ]);
+                    hud.write(&queue, &text);
+                }
 
                 let render_command_buffer = command_buffer::create(
                     &device,
@@ -722,15 +2076,73 @@ fn main() {
                             },
                         );
                         command_encoder.pop_debug_group();
+
+                        if hud_visible {
+                            hud.draw(command_encoder, &surface_texture_view);
+                        }
                     },
                 );
 
                 trace!("submitting render commands");
                 queue.submit([render_command_buffer]);
 
-                surface_texture.present();
+                // Everything needed to render the frame again, at `size` after supersampling.
+                let current_scene = |size, supersampling| Scene {
+                    view: Some(view.clone()),
+                    formula: Some(formula),
+                    formula_file: custom_formula_path.clone(),
+                    exponent: Some(exponent),
+                    polynomial: Some(polynomial.clone()),
+                    relaxation: Some(relaxation),
+                    phoenix_parameter: Some(phoenix_parameter),
+                    iteration_limit: Some(iteration_limit),
+                    colouring: Some(colouring),
+                    palette: Some(palette),
+                    size: Some(size),
+                    supersampling: Some(supersampling),
+                };
+
+                if scene_save_requested {
+                    scene_save_requested = false;
+
+                    let seconds = SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map_or(0, |duration| duration.as_secs());
+                    let path = PathBuf::from(format!("wgpu-mandelbrot-{}.toml", seconds));
+                    // The window is often smaller than the image that the scene is for, so the
+                    // scene's size is kept.
+                    let current_scene =
+                        current_scene(scene.size.unwrap_or(screen_size), supersampling);
+                    match scene::save(&path, &current_scene) {
+                        Ok(()) => eprintln!("saved scene to {}", path.display()),
+                        Err(err) => eprintln!("error: {}", err),
+                    }
+                }
+
+                if screenshot_requested {
+                    screenshot_requested = false;
+
+                    let seconds = SystemTime::now()
+                        .duration_since(SystemTime::UNIX_EPOCH)
+                        .map_or(0, |duration| duration.as_secs());
+                    let path = PathBuf::from(format!("wgpu-mandelbrot-{}.png", seconds));
+                    let text = image_text(&current_scene(screen_size, 1));
+                    let result = screenshot::capture(
+                        &device,
+                        &queue,
+                        screen_size,
+                        surface_configuration.format,
+                        &render_pipeline,
+                        &[&render_bind_group_1, &render_bind_group_2],
+                    )
+                    .and_then(|image| image.save(&path, &text));
+                    match result {
+                        Ok(()) => eprintln!("saved screenshot to {}", path.display()),
+                        Err(err) => eprintln!("error: {}", err),
+                    }
+                }
 
-                pixels_buffers.swap();
+                surface_texture.present();
             }
             _ => {}
         }
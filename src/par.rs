@@ -0,0 +1,383 @@
+/*!
+[Fractint](https://fractint.org/) `.par` parameter files.
+
+A `.par` file holds named entries, of which only Mandelbrot set entries are used, and the rest
+are skipped:
+
+```text
+Seahorse_valley {
+  reset=2004 type=mandel center-mag=-0.74364388703716/0.13182590420531/1e+09
+  params=0/0 float=y maxiter=1500 ; comments run to the end of the line
+}
+```
+
+The location is read from `center-mag=<x>/<y>/<mag>`, where `mag = 1` shows from `-1` to `1`
+vertically, or from `corners=<xmin>/<xmax>/<ymin>/<ymax>`. The rest of `center-mag` (the aspect
+ratio, rotation and skew) is ignored, as are colours.
+*/
+
+use std::{fmt::Display, str::FromStr};
+
+use dashu_float::DBig;
+use log::warn;
+
+use crate::{bookmarks::Bookmark, formula::Formula, view::View};
+
+/// The Fractint types that are the Mandelbrot set.
+const MANDELBROT_TYPES: [&str; 2] = ["mandel", "mandelfp"];
+
+/// The longest name that Fractint accepts for an entry.
+const MAX_NAME_LENGTH: usize = 30;
+
+/**
+Read the entries in a `.par` file, as bookmarks named after the entries.
+
+Entries for other fractals, or that can't be read, are skipped with a warning, as long as at
+least one entry can be read. An entry without a closing `}` is skipped along with the rest of the
+file, which can't be split into entries.
+*/
+pub fn parse(source: &str) -> Result<Vec<Bookmark>, ParseParError> {
+    // Comments run from `;` to the end of the line, and lines ending in `\` continue onto the next.
+    let source = source
+        .lines()
+        .map(|line| line.split_once(';').map_or(line, |(line, _)| line))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .replace("\\\n", "");
+
+    let mut bookmarks = Vec::new();
+    let mut first_error = None;
+    let mut rest = source.as_str();
+    while let Some((name, after_name)) = rest.split_once('{') {
+        let name = name.trim().to_string();
+        // An entry that's followed by another before it's closed is unclosed too.
+        let Some((body, after_body)) = after_name
+            .split_once('}')
+            .filter(|(body, _)| !body.contains('{'))
+        else {
+            let err = ParseParError::UnclosedEntry(name);
+            warn!("skipping the rest of the file: {}", err);
+            first_error.get_or_insert(err);
+            rest = "";
+            break;
+        };
+        match parse_entry(&name, body) {
+            Ok(bookmark) => bookmarks.push(bookmark),
+            Err(err) => {
+                warn!("skipping entry {:?}: {}", name, err);
+                first_error.get_or_insert(ParseParError::Entry { name, err });
+            }
+        }
+        rest = after_body;
+    }
+
+    if !rest.trim().is_empty() {
+        return Err(ParseParError::Unexpected(rest.trim().to_string()));
+    }
+    if bookmarks.is_empty() {
+        return Err(first_error.unwrap_or(ParseParError::NoEntries));
+    }
+    Ok(bookmarks)
+}
+
+fn parse_entry(name: &str, body: &str) -> Result<Bookmark, ParseParEntryError> {
+    let parameter = |key: &str| {
+        body.split_whitespace()
+            .filter_map(|parameter| parameter.split_once('='))
+            .find(|(parameter_key, _)| parameter_key.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    };
+    let invalid = |key: &'static str, value: &str| ParseParEntryError::InvalidParameter {
+        key,
+        value: value.to_string(),
+    };
+    let decimal = |key: &'static str, value: &str| {
+        DBig::from_str(value.trim_start_matches('+')).map_err(|_| invalid(key, value))
+    };
+
+    let fractal_type = parameter("type").unwrap_or("mandel");
+    if !MANDELBROT_TYPES.contains(&fractal_type.to_ascii_lowercase().as_str()) {
+        return Err(ParseParEntryError::UnsupportedType(
+            fractal_type.to_string(),
+        ));
+    }
+
+    let view = if let Some(center_mag) = parameter("center-mag") {
+        let parts = center_mag.split('/').collect::<Vec<_>>();
+        let [x, y, mag, ..] = parts[..] else {
+            return Err(invalid("center-mag", center_mag));
+        };
+        let mag = mag
+            .parse::<f64>()
+            .ok()
+            .map(|mag| 2.0 * mag)
+            .filter(|zoom| zoom.is_finite() && *zoom > 0.0)
+            .ok_or_else(|| invalid("center-mag", center_mag))?;
+        View::new(decimal("center-mag", x)?, decimal("center-mag", y)?, mag)
+    } else if let Some(corners) = parameter("corners") {
+        let parts = corners
+            .split('/')
+            .map(|part| decimal("corners", part))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [x_min, x_max, y_min, y_max, ..] = &parts[..] else {
+            return Err(invalid("corners", corners));
+        };
+        let half = DBig::from_str("0.5").unwrap();
+        let height = (y_max - y_min).to_f64().value().abs();
+        let zoom = 4.0 / height;
+        if !(zoom.is_finite() && zoom > 0.0) {
+            return Err(invalid("corners", corners));
+        }
+        View::new((x_min + x_max) * &half, (y_min + y_max) * &half, zoom)
+    } else {
+        return Err(ParseParEntryError::MissingLocation);
+    };
+
+    let iteration_limit = parameter("maxiter")
+        .map(|maxiter| {
+            maxiter
+                .parse::<u32>()
+                .ok()
+                .filter(|maxiter| *maxiter > 0)
+                .ok_or_else(|| invalid("maxiter", maxiter))
+        })
+        .transpose()?;
+
+    Ok(Bookmark {
+        name: name.to_string(),
+        view,
+        formula: Some(Formula::Multibrot),
+        exponent: Some(2.0),
+        iteration_limit,
+        palette: None,
+    })
+}
+
+/**
+Write `bookmark` as a `.par` entry.
+
+Fractint's names can't contain spaces, so the name is changed to fit.
+*/
+pub fn write(bookmark: &Bookmark) -> Result<String, WriteParError> {
+    let formula = bookmark.formula.unwrap_or_default();
+    if formula != Formula::Multibrot {
+        return Err(WriteParError::UnsupportedFormula(formula));
+    }
+    let exponent = bookmark.exponent.unwrap_or(2.0);
+    if exponent != 2.0 {
+        return Err(WriteParError::UnsupportedExponent(exponent));
+    }
+
+    let mut name = bookmark
+        .name
+        .chars()
+        .map(|character| {
+            if character.is_ascii_alphanumeric() || "_-.".contains(character) {
+                character
+            } else {
+                '_'
+            }
+        })
+        .take(MAX_NAME_LENGTH)
+        .collect::<String>();
+    if name.is_empty() {
+        name = "wgpu_mandelbrot".to_string();
+    }
+
+    let mut par = format!(
+        "{} {{\n  reset=2004 type=mandel center-mag={}/{}/{:e}\n  params=0/0 float=y",
+        name,
+        bookmark.view.real(),
+        bookmark.view.imaginary(),
+        bookmark.view.zoom() / 2.0
+    );
+    if let Some(iteration_limit) = bookmark.iteration_limit {
+        par += &format!(" maxiter={}", iteration_limit);
+    }
+    par += "\n}\n";
+    Ok(par)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseParError {
+    NoEntries,
+    UnclosedEntry(String),
+    Unexpected(String),
+    Entry {
+        name: String,
+        err: ParseParEntryError,
+    },
+}
+
+impl Display for ParseParError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseParError::NoEntries => write!(f, "no entries"),
+            ParseParError::UnclosedEntry(name) => write!(f, "entry {:?} has no closing `}}`", name),
+            ParseParError::Unexpected(text) => write!(f, "unexpected text: {:?}", text),
+            ParseParError::Entry { name, err } => write!(f, "in entry {:?}: {}", name, err),
+        }
+    }
+}
+
+impl std::error::Error for ParseParError {}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseParEntryError {
+    UnsupportedType(String),
+    MissingLocation,
+    InvalidParameter { key: &'static str, value: String },
+}
+
+impl Display for ParseParEntryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseParEntryError::UnsupportedType(fractal_type) => {
+                write!(f, "unsupported type: {:?}", fractal_type)
+            }
+            ParseParEntryError::MissingLocation => {
+                write!(f, "missing `center-mag` or `corners`")
+            }
+            ParseParEntryError::InvalidParameter { key, value } => {
+                write!(f, "invalid {}: {:?}", key, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseParEntryError {}
+
+#[derive(Debug, PartialEq)]
+pub enum WriteParError {
+    UnsupportedFormula(Formula),
+    UnsupportedExponent(f32),
+}
+
+impl Display for WriteParError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteParError::UnsupportedFormula(formula) => {
+                write!(
+                    f,
+                    "Fractint files only support the Mandelbrot set, not {}",
+                    formula.name()
+                )
+            }
+            WriteParError::UnsupportedExponent(exponent) => write!(
+                f,
+                "Fractint files only support the Mandelbrot set, not an exponent of {}",
+                exponent
+            ),
+        }
+    }
+}
+
+impl std::error::Error for WriteParError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn written_entries_parse_back() {
+        let bookmark = Bookmark {
+            name: "Seahorse valley".to_string(),
+            view: "-0.74364388703715870475219150 0.13182590420531197049 2e20"
+                .parse()
+                .unwrap(),
+            formula: Some(Formula::Multibrot),
+            exponent: Some(2.0),
+            iteration_limit: Some(1500),
+            palette: None,
+        };
+
+        let bookmarks = parse(&write(&bookmark).unwrap()).unwrap();
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].name, "Seahorse_valley");
+        assert_eq!(bookmarks[0].view, bookmark.view);
+        assert_eq!(bookmarks[0].iteration_limit, Some(1500));
+    }
+
+    #[test]
+    fn other_fractals_cant_be_written() {
+        let mut bookmark = Bookmark {
+            name: "a".to_string(),
+            view: View::default(),
+            formula: Some(Formula::BurningShip),
+            exponent: None,
+            iteration_limit: None,
+            palette: None,
+        };
+        assert_eq!(
+            write(&bookmark),
+            Err(WriteParError::UnsupportedFormula(Formula::BurningShip))
+        );
+        bookmark.formula = None;
+        bookmark.exponent = Some(3.0);
+        assert_eq!(
+            write(&bookmark),
+            Err(WriteParError::UnsupportedExponent(3.0))
+        );
+    }
+
+    #[test]
+    fn unusable_entries_are_skipped() {
+        let source = "
+            Julia { type=julia center-mag=0/0/1 params=-0.8/0.156 }
+            Seahorse_valley { ; a comment
+              reset=2004 type=mandel center-mag=-0.75/0.1/1e+09 \\
+              maxiter=1500
+            }
+            Broken { type=mandel center-mag=-0.75/0.1/0 }
+            Corners { type=mandelfp corners=-2/1/-1.5/1.5 }
+            Unclosed { type=mandel center-mag=0/0/1
+            After_unclosed { type=mandel center-mag=0/0/1 }
+        ";
+
+        let bookmarks = parse(source).unwrap();
+        let names = bookmarks
+            .iter()
+            .map(|bookmark| bookmark.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Seahorse_valley", "Corners"]);
+        assert_eq!(bookmarks[0].view, "-0.75 0.1 2e9".parse().unwrap());
+        assert_eq!(bookmarks[0].iteration_limit, Some(1500));
+        assert_eq!(
+            bookmarks[1].view,
+            "-0.5 0 1.3333333333333333".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn files_without_usable_entries_are_rejected() {
+        assert_eq!(
+            parse("Julia { type=julia center-mag=0/0/1 }").unwrap_err(),
+            ParseParError::Entry {
+                name: "Julia".to_string(),
+                err: ParseParEntryError::UnsupportedType("julia".to_string()),
+            }
+        );
+        assert_eq!(parse("").unwrap_err(), ParseParError::NoEntries);
+        assert_eq!(
+            parse("a { center-mag=0/0/1").unwrap_err(),
+            ParseParError::UnclosedEntry("a".to_string())
+        );
+        for body in [
+            "center-mag=0/0/0",
+            "center-mag=0/0/-1",
+            "center-mag=0/0/1 maxiter=0",
+        ] {
+            assert!(
+                matches!(
+                    parse(&format!("a {{ {} }}", body)),
+                    Err(ParseParError::Entry {
+                        err: ParseParEntryError::InvalidParameter { .. },
+                        ..
+                    })
+                ),
+                "{}",
+                body
+            );
+        }
+    }
+}
@@ -0,0 +1,437 @@
+/*!
+Perturbation theory, for zooming past the precision of `f32`.
+
+At a zoom of about `1e5`, neighbouring pixels' values of `c` are no longer distinct `f32`s, and
+the image turns into blocks. Instead of iterating each pixel's `z` directly, we iterate a single
+*reference orbit* `Z` in arbitrary precision on the CPU, and each pixel's difference from it on
+the GPU (`compute.wgsl#perturbation`).
+
+For the Mandelbrot set, a pixel with `c = C + dc` has `z = Z + dz`, where
+
+```text
+dz' = 2 * Z * dz + dz^2 + dc
+```
+
+`dz` and `dc` are tiny, but only their *relative* precision matters, so `f32` mantissas are
+enough. Their exponents can be far smaller than `f32` allows, so they're stored as a [`Scaled`]
+mantissa and exponent.
+
+A pixel's orbit can stray far enough from the reference that the difference loses precision
+(a "glitch"). We detect this when `|Z + dz| < |dz|`, and rebase the pixel onto the start of the
+reference orbit, with `dz = Z + dz`. This is also how pixels continue past the end of the
+reference orbit. See [Zhuoran's rebasing](https://fractalforums.org/fractal-mathematics-and-new-theories/28/another-solution-to-perturbation-glitches/4360).
+
+Every pixel near the reference point follows the same early part of its orbit, so a
+[`SeriesApproximation`] lets pixels skip it.
+*/
+
+use std::ops::{Add, Mul};
+
+use bytemuck::{Pod, Zeroable};
+use dashu_float::FBig;
+
+use crate::pixel::Complex;
+
+/// The number of bits of precision needed to locate pixels at `zoom`.
+pub fn precision(zoom: f64) -> usize {
+    // Enough for a 4096 pixel wide screen, with 32 bits to spare.
+    const EXTRA_BITS: usize = 12 + 32;
+
+    zoom.log2().max(0.0).ceil() as usize + EXTRA_BITS
+}
+
+/// Arbitrary-precision complex numbers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BigComplex {
+    pub real: FBig,
+    pub imaginary: FBig,
+}
+
+impl BigComplex {
+    pub fn from_f64(real: f64, imaginary: f64, precision: usize) -> Self {
+        BigComplex {
+            real: FBig::try_from(real).unwrap(),
+            imaginary: FBig::try_from(imaginary).unwrap(),
+        }
+        .with_precision(precision)
+    }
+
+    /// Round (or extend) both components to `precision` bits.
+    pub fn with_precision(&self, precision: usize) -> Self {
+        BigComplex {
+            real: self.real.clone().with_precision(precision).value(),
+            imaginary: self.imaginary.clone().with_precision(precision).value(),
+        }
+    }
+
+    /// Round to the nearest [`Complex`].
+    pub fn to_complex(&self) -> Complex {
+        Complex::new(self.real.to_f32().value(), self.imaginary.to_f32().value())
+    }
+
+    pub fn add(&self, other: &BigComplex) -> BigComplex {
+        BigComplex {
+            real: &self.real + &other.real,
+            imaginary: &self.imaginary + &other.imaginary,
+        }
+    }
+
+    pub fn square(&self) -> BigComplex {
+        BigComplex {
+            real: self.real.sqr() - self.imaginary.sqr(),
+            imaginary: (&self.real * &self.imaginary) * 2,
+        }
+    }
+}
+
+/**
+`mantissa * 2^exponent`, for numbers outside the range of `f32`.
+
+Corresponds to `compute.wgsl#Scaled`.
+*/
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq)]
+pub struct Scaled {
+    pub mantissa: f32,
+    pub exponent: i32,
+}
+
+/// The mantissa is in `[1, 2)`, or zero.
+impl From<f64> for Scaled {
+    fn from(value: f64) -> Self {
+        if value == 0.0 {
+            return Scaled {
+                mantissa: 0.0,
+                exponent: 0,
+            };
+        }
+
+        let exponent = value.abs().log2().floor() as i32;
+        Scaled {
+            mantissa: (value / 2.0_f64.powi(exponent)) as f32,
+            exponent,
+        }
+    }
+}
+
+/**
+The orbit of the reference point `C`: `Z_0 = 0`, `Z_(n+1) = Z_n^2 + C`.
+
+Each `Z_n` is computed in the precision of `C`, and rounded to a [`Complex`] for the GPU
+(`compute.wgsl#reference_orbit`). The orbit ends early if `C` escapes.
+*/
+pub struct ReferenceOrbit {
+    c: BigComplex,
+    value: BigComplex,
+    values: Vec<Complex>,
+    escaped: bool,
+}
+
+impl ReferenceOrbit {
+    /// The smallest number of values that [`ReferenceOrbit::ensure_length`] computes at once.
+    const MIN_LENGTH: usize = 1024;
+
+    /// Starts with only `Z_0`; see [`ReferenceOrbit::ensure_length`].
+    pub fn new(c: BigComplex) -> Self {
+        let precision = c.real.precision().max(c.imaginary.precision());
+        ReferenceOrbit {
+            value: BigComplex::from_f64(0.0, 0.0, precision),
+            c,
+            values: vec![Complex::ZERO],
+            escaped: false,
+        }
+    }
+
+    pub fn c(&self) -> &BigComplex {
+        &self.c
+    }
+
+    pub fn values(&self) -> &[Complex] {
+        &self.values
+    }
+
+    /// Whether `C` escaped, which means there are no values after the last one.
+    pub fn escaped(&self) -> bool {
+        self.escaped
+    }
+
+    /**
+    Extend the orbit so that it has at least `length` values, unless `C` escapes first.
+
+    The orbit grows to the next power of two, so that it (and its buffer) only needs to be
+    extended occasionally. Returns whether any values were added.
+    */
+    pub fn ensure_length(&mut self, length: usize) -> bool {
+        if self.escaped || self.values.len() >= length {
+            return false;
+        }
+
+        let length = length.next_power_of_two().max(Self::MIN_LENGTH);
+        while self.values.len() < length {
+            self.value = self.value.square().add(&self.c);
+
+            let value = self.value.to_complex();
+            self.values.push(value);
+
+            if value.length() > 2.0 {
+                self.escaped = true;
+                break;
+            }
+        }
+
+        true
+    }
+}
+
+/**
+Approximates the perturbation of every pixel for the first `iterations` iterations, so that
+pixels can skip them.
+
+The perturbation `dz_n` is approximated by a polynomial in `dc`:
+
+```text
+dz_n = A_n * dc + B_n * dc^2 + C_n * dc^3
+
+A_(n+1) = 2 * Z_n * A_n + 1
+B_(n+1) = 2 * Z_n * B_n + A_n^2
+C_(n+1) = 2 * Z_n * C_n + 2 * A_n * B_n
+```
+
+The approximation is valid while the cubic term is negligible for every pixel. Pixels are
+within a radius of `sqrt(2) * scale` of the reference point (see `compute.wgsl#scale`), and `A`,
+`B` and `C` grow too quickly for `f64`, so we store them in terms of `u = dc / scale`:
+`a_n = A_n * scale`, `b_n = B_n * scale^2` and `c_n = C_n * scale^3`.
+
+Corresponds to `compute.wgsl#SeriesApproximation`.
+*/
+#[repr(C)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq)]
+pub struct SeriesApproximation {
+    /// The mantissa of `a_n`, relative to `2^exponent`.
+    pub a: Complex,
+    /// The mantissa of `b_n`, relative to `2^exponent`.
+    pub b: Complex,
+    /// The mantissa of `c_n`, relative to `2^exponent`.
+    pub c: Complex,
+    pub exponent: i32,
+    /// The number of iterations `n` that the approximation skips.
+    pub iterations: u32,
+}
+
+impl SeriesApproximation {
+    /// The largest allowed ratio of the cubic term to the linear term, for any pixel.
+    const TOLERANCE: f64 = 1.0 / (1 << 24) as f64;
+
+    /// The most iterations to skip, so that reference points inside the set terminate.
+    const MAX_ITERATIONS: usize = 1 << 14;
+
+    /// Skips nothing.
+    pub const NONE: SeriesApproximation = SeriesApproximation {
+        a: Complex::ZERO,
+        b: Complex::ZERO,
+        c: Complex::ZERO,
+        exponent: 0,
+        iterations: 0,
+    };
+
+    /**
+    Skip as many iterations of `reference_orbit` as possible for a view with `scale`,
+    extending the orbit as needed.
+    */
+    pub fn new(reference_orbit: &mut ReferenceOrbit, scale: f64) -> Self {
+        // `|u| <= sqrt(2)`, so the cubic term is up to `2` times larger relative to the linear term.
+        const MAX_U_SQUARED: f64 = 2.0;
+
+        let mut a = Complex64::ZERO;
+        let mut b = Complex64::ZERO;
+        let mut c = Complex64::ZERO;
+        let mut iterations = 0;
+
+        while iterations < Self::MAX_ITERATIONS {
+            // `compute.wgsl#perturbation` needs the value after the last skipped iteration, and
+            // the one after that.
+            reference_orbit.ensure_length(iterations + 3);
+            if reference_orbit.values().len() < iterations + 3 {
+                break;
+            }
+
+            let z = Complex64::from(reference_orbit.values()[iterations]);
+            let two_z = z * 2.0;
+            let next_a = two_z * a + Complex64::new(scale, 0.0);
+            let next_b = two_z * b + a * a;
+            let next_c = two_z * c + a * b * 2.0;
+
+            if next_c.length() * MAX_U_SQUARED > Self::TOLERANCE * next_a.length()
+                || !next_c.length().is_finite()
+            {
+                break;
+            }
+
+            a = next_a;
+            b = next_b;
+            c = next_c;
+            iterations += 1;
+        }
+
+        if iterations == 0 {
+            return Self::NONE;
+        }
+
+        let largest = a.length().max(b.length()).max(c.length());
+        let exponent = largest.log2().floor() as i32;
+        let mantissa = |value: Complex64| (value * 2.0_f64.powi(-exponent)).to_complex();
+        SeriesApproximation {
+            a: mantissa(a),
+            b: mantissa(b),
+            c: mantissa(c),
+            exponent,
+            iterations: iterations as u32,
+        }
+    }
+}
+
+/// Complex numbers for host-side calculations that need more range than [`Complex`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Complex64 {
+    real: f64,
+    imaginary: f64,
+}
+
+impl Complex64 {
+    const ZERO: Self = Complex64 {
+        real: 0.0,
+        imaginary: 0.0,
+    };
+
+    fn new(real: f64, imaginary: f64) -> Self {
+        Complex64 { real, imaginary }
+    }
+
+    fn length(self) -> f64 {
+        self.real.hypot(self.imaginary)
+    }
+
+    fn to_complex(self) -> Complex {
+        Complex::new(self.real as f32, self.imaginary as f32)
+    }
+}
+
+impl From<Complex> for Complex64 {
+    fn from(value: Complex) -> Self {
+        Complex64::new(value.real as f64, value.imaginary as f64)
+    }
+}
+
+impl Add for Complex64 {
+    type Output = Complex64;
+
+    fn add(self, other: Complex64) -> Complex64 {
+        Complex64::new(self.real + other.real, self.imaginary + other.imaginary)
+    }
+}
+
+impl Mul for Complex64 {
+    type Output = Complex64;
+
+    fn mul(self, other: Complex64) -> Complex64 {
+        Complex64::new(
+            self.real * other.real - self.imaginary * other.imaginary,
+            self.real * other.imaginary + self.imaginary * other.real,
+        )
+    }
+}
+
+impl Mul<f64> for Complex64 {
+    type Output = Complex64;
+
+    fn mul(self, other: f64) -> Complex64 {
+        Complex64::new(self.real * other, self.imaginary * other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reference_orbits_match_direct_iteration() {
+        // Inside the main cardioid, where rounding errors don't grow.
+        let (c_real, c_imaginary) = (-0.1, 0.6);
+        let mut reference_orbit =
+            ReferenceOrbit::new(BigComplex::from_f64(c_real, c_imaginary, precision(1.0)));
+        reference_orbit.ensure_length(100);
+        assert!(!reference_orbit.escaped());
+
+        let c = Complex64::new(c_real, c_imaginary);
+        let mut z = Complex64::ZERO;
+        for (n, value) in reference_orbit.values()[..100].iter().enumerate() {
+            // `C` is rounded to fewer bits than an `f64` has.
+            let error = (*value - z.to_complex()).length();
+            assert!(error <= 1e-6, "error of {} at Z_{}", error, n);
+            z = z * z + c;
+        }
+    }
+
+    #[test]
+    fn reference_orbits_end_when_they_escape() {
+        let (c_real, c_imaginary) = (0.5, 0.5);
+        let mut reference_orbit =
+            ReferenceOrbit::new(BigComplex::from_f64(c_real, c_imaginary, precision(1.0)));
+        assert!(reference_orbit.ensure_length(100));
+        assert!(reference_orbit.escaped());
+        assert!(!reference_orbit.ensure_length(200));
+
+        let c = Complex64::new(c_real, c_imaginary);
+        let mut z = Complex64::ZERO;
+        let mut length = 1;
+        while z.length() <= 2.0 {
+            z = z * z + c;
+            length += 1;
+        }
+        assert_eq!(reference_orbit.values().len(), length);
+    }
+
+    #[test]
+    fn series_approximations_stay_within_tolerance() {
+        let scale = 1e-12;
+        let mut reference_orbit = ReferenceOrbit::new(BigComplex::from_f64(
+            -0.743643887037158,
+            0.131825904205312,
+            precision(1.0 / scale),
+        ));
+        let series_approximation = SeriesApproximation::new(&mut reference_orbit, scale);
+        let iterations = series_approximation.iterations as usize;
+        assert!(iterations > 0);
+
+        let coefficient =
+            |value: Complex| Complex64::from(value) * 2.0_f64.powi(series_approximation.exponent);
+        let a = coefficient(series_approximation.a);
+        let b = coefficient(series_approximation.b);
+        let c = coefficient(series_approximation.c);
+
+        // The corners of the screen are the furthest pixels from the reference point.
+        for u in [
+            Complex64::new(1.0, 1.0),
+            Complex64::new(-1.0, 1.0),
+            Complex64::new(1.0, -1.0),
+            Complex64::new(-1.0, -1.0),
+            Complex64::new(0.5, 0.0),
+        ] {
+            let dc = u * scale;
+            let mut dz = Complex64::ZERO;
+            for value in &reference_orbit.values()[..iterations] {
+                let z = Complex64::from(*value);
+                dz = z * dz * 2.0 + dz * dz + dc;
+            }
+
+            let approximation = a * u + b * u * u + c * u * u * u;
+            let error = (approximation + dz * -1.0).length() / dz.length();
+            assert!(
+                error <= SeriesApproximation::TOLERANCE,
+                "error of {} at {:?}",
+                error,
+                u
+            );
+        }
+    }
+}
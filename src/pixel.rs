@@ -1,10 +1,16 @@
 //! Pixel data.
 
+use std::{
+    fmt::Display,
+    ops::{Add, Div, Mul, Sub},
+    str::FromStr,
+};
+
 use bytemuck::{Pod, Zeroable};
 
 /// [`bytemuck`]-compatible complex numbers.
 #[repr(C)]
-#[derive(Pod, Zeroable, Clone, Copy, Debug)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq)]
 pub struct Complex {
     pub real: f32,
     pub imaginary: f32,
@@ -15,6 +21,124 @@ impl Complex {
         real: 0.0,
         imaginary: 0.0,
     };
+
+    pub const ONE: Self = Complex {
+        real: 1.0,
+        imaginary: 0.0,
+    };
+
+    pub fn new(real: f32, imaginary: f32) -> Self {
+        Complex { real, imaginary }
+    }
+
+    /// The absolute value (modulus).
+    pub fn length(self) -> f32 {
+        self.real.hypot(self.imaginary)
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.real + other.real, self.imaginary + other.imaginary)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.real - other.real, self.imaginary - other.imaginary)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.real * other.real - self.imaginary * other.imaginary,
+            self.real * other.imaginary + self.imaginary * other.real,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Complex;
+
+    fn div(self, other: Complex) -> Complex {
+        let denominator = other.real * other.real + other.imaginary * other.imaginary;
+        Complex::new(
+            (self.real * other.real + self.imaginary * other.imaginary) / denominator,
+            (self.imaginary * other.real - self.real * other.imaginary) / denominator,
+        )
+    }
+}
+
+/// Formats as `a` or `a+bi`, the same syntax accepted by [`Complex::from_str`].
+impl Display for Complex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.imaginary == 0.0 {
+            write!(f, "{}", self.real)
+        } else if self.imaginary.is_sign_negative() {
+            write!(f, "{}-{}i", self.real, -self.imaginary)
+        } else {
+            write!(f, "{}+{}i", self.real, self.imaginary)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ParseComplexError(String);
+
+impl Display for ParseComplexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid complex number: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseComplexError {}
+
+/**
+Parses `a`, `bi`, `i`, `a+bi` and `a-bi`, where `a` and `b` are [`f32`]s.
+
+Whitespace is not allowed.
+*/
+impl FromStr for Complex {
+    type Err = ParseComplexError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let error = || ParseComplexError(input.to_string());
+
+        let parse_imaginary = |value: &str| -> Result<f32, ParseComplexError> {
+            match value {
+                "" | "+" => Ok(1.0),
+                "-" => Ok(-1.0),
+                _ => value.parse().map_err(|_| error()),
+            }
+        };
+
+        match input.strip_suffix('i') {
+            None => Ok(Complex::new(input.parse().map_err(|_| error())?, 0.0)),
+            Some(rest) => {
+                // The sign that separates the real and imaginary parts is the last `+` or `-`
+                // that isn't at the start of the input or part of an exponent (`1e-3`).
+                let separator = rest.char_indices().rev().find(|(index, char)| {
+                    (*char == '+' || *char == '-')
+                        && *index > 0
+                        && !rest[..*index].ends_with(['e', 'E'])
+                });
+                match separator {
+                    None => Ok(Complex::new(0.0, parse_imaginary(rest)?)),
+                    Some((index, _)) => Ok(Complex::new(
+                        rest[..index].parse().map_err(|_| error())?,
+                        parse_imaginary(&rest[index..])?,
+                    )),
+                }
+            }
+        }
+    }
 }
 
 /// Pixel data for rendering fractals.
@@ -26,4 +150,49 @@ pub struct Pixel {
     pub escaped: u32,
     pub current_value: Complex,
     pub iteration_count: u32,
+    /// The value before `current_value`, for formulas that depend on the previous iterate.
+    pub previous_value: Complex,
+    /// State that belongs to the [`Engine`](crate::compute::Engine) that iterates the pixel.
+    /// Corresponds to `compute.wgsl#Pixel.engine_state`.
+    pub engine_state: [u32; 4],
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complex_numbers_are_parsed() {
+        for (input, expected) in [
+            ("1+0.5i", Complex::new(1.0, 0.5)),
+            ("1-0.5i", Complex::new(1.0, -0.5)),
+            ("-0.5", Complex::new(-0.5, 0.0)),
+            ("i", Complex::new(0.0, 1.0)),
+            ("-i", Complex::new(0.0, -1.0)),
+            ("2.5i", Complex::new(0.0, 2.5)),
+            ("-1+i", Complex::new(-1.0, 1.0)),
+            ("1e-3-2E+2i", Complex::new(1e-3, -2e2)),
+        ] {
+            assert_eq!(input.parse::<Complex>().unwrap(), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn invalid_complex_numbers_are_rejected() {
+        for input in ["", "x", "1+", "1+2", "1 + 2i", "1+2j", "ii", "1+2i+3i"] {
+            assert!(input.parse::<Complex>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn complex_numbers_are_formatted_and_parsed_back() {
+        for complex in [
+            Complex::new(1.0, 0.5),
+            Complex::new(-0.5, 0.0),
+            Complex::new(0.0, 1.0),
+            Complex::new(1e-3, -2e2),
+        ] {
+            assert_eq!(complex.to_string().parse::<Complex>().unwrap(), complex);
+        }
+    }
 }
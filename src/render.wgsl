@@ -10,32 +10,99 @@ fn vertex_main(@builtin(vertex_index) index : u32) -> @builtin(position) vec4<f3
   return vec4<f32>(vertices[index], 0.0, 1.0);
 }
 
-struct ColourRange{escaped : u32, value : f32}
+struct ColourRange{escaped : u32, value : f32, root : u32}
 
 @group(0) @binding(0) var<uniform> screen_size : vec2<u32>;
+// The box being dragged out to zoom into, from the corner `xy` to the corner `zw`, in pixels.
+// There's no box when the corners are equal.
+@group(0) @binding(1) var<uniform> selection : vec4<f32>;
+// Corresponds to `colour.rs#Palette`.
+@group(0) @binding(2) var<uniform> palette : u32;
 
 @group(1) @binding(0) var<storage, read> colour_ranges : array<ColourRange>;
   
 let GAMMA = vec3<f32>(2.2, 2.2, 2.2);
 let EXPONENT = vec3<f32>(2.0, 2.0, 2.0);
 
+// Convert a colour from HSV to RGB, with all components in `[0, 1]`.
+fn hsv_to_rgb(hue : f32, saturation : f32, value : f32) -> vec3<f32> {
+  let rgb = clamp(
+    abs(fract(vec3<f32>(hue, hue, hue) + vec3<f32>(1.0, 2.0 / 3.0, 1.0 / 3.0)) * 6.0 - 3.0) - 1.0,
+    vec3<f32>(0.0, 0.0, 0.0),
+    vec3<f32>(1.0, 1.0, 1.0)
+  );
+  return value * mix(vec3<f32>(1.0, 1.0, 1.0), rgb, saturation);
+}
+
+/*
+The colour of the basin for `root` (see `colour.rs#ColourRange`), shaded darker the longer
+the pixel took to converge.
+
+Hues are spread using the golden ratio, so neighbouring roots have distinct colours no matter
+how many roots there are.
+*/
+fn root_colour(colour_range : ColourRange) -> vec4<f32> {
+  let hue = fract(f32(colour_range.root - 1u) * 0.618034);
+  let colour = pow(hsv_to_rgb(hue, 0.7, 0.9), GAMMA);
+  return vec4<f32>(colour * (1.0 - 0.85 * colour_range.value), 1.0);
+}
+
+/*
+The colour at `position` in `[0, 1]` along `palette`.
+
+Each palette is a gradient through three sRGB colours. The middle colour of `PALETTE_GREEN` is
+halfway between the others in linear RGB, so it's a plain two-colour gradient.
+*/
+fn palette_colour(position : f32) -> vec3<f32> {
+  var stops = array<vec3<f32>, 12>(
+    // PALETTE_GREEN
+    vec3<f32>(15.0, 66.0, 7.0), vec3<f32>(186.0, 190.0, 186.0), vec3<f32>(255.0, 255.0, 255.0),
+    // PALETTE_FIRE
+    vec3<f32>(40.0, 0.0, 0.0), vec3<f32>(230.0, 80.0, 0.0), vec3<f32>(255.0, 240.0, 160.0),
+    // PALETTE_OCEAN
+    vec3<f32>(0.0, 12.0, 45.0), vec3<f32>(0.0, 110.0, 190.0), vec3<f32>(220.0, 250.0, 255.0),
+    // PALETTE_GRAYSCALE
+    vec3<f32>(0.0, 0.0, 0.0), vec3<f32>(186.0, 186.0, 186.0), vec3<f32>(255.0, 255.0, 255.0)
+  );
+
+  let first = pow(stops[palette * 3u] / 255.0, GAMMA);
+  let middle = pow(stops[palette * 3u + 1u] / 255.0, GAMMA);
+  let last = pow(stops[palette * 3u + 2u] / 255.0, GAMMA);
+
+  if position < 0.5 {
+    return mix(first, middle, position * 2.0);
+  }
+  return mix(middle, last, position * 2.0 - 1.0);
+}
+
 fn compute_colour(colour_range : ColourRange) -> vec4<f32> {
+  if colour_range.escaped == 1u && colour_range.root != 0u {
+    return root_colour(colour_range);
+  }
+
   let unescaped = pow(vec3<f32>(0.0, 0.0, 0.0), GAMMA);
-  let initial_colour = pow(vec3<f32>(15.0 / 255.0, 66.0 / 255.0, 7.0 / 255.0), GAMMA);
-  let final_colour = pow(vec3<f32>(1.0, 1.0, 1.0), GAMMA);
-  
-  let scale = vec3<f32>(colour_range.value, colour_range.value, colour_range.value);
   
   if colour_range.escaped == 1u {
-    return vec4<f32>(
-      initial_colour + (final_colour - initial_colour) * pow(scale, EXPONENT),
-      1.0
-    );
+    return vec4<f32>(palette_colour(pow(colour_range.value, EXPONENT.x)), 1.0);
   } else {
     return vec4<f32>(unescaped, 1.0);
   }
 }
 
+// Outline `selection`, and lighten what's inside it.
+fn overlay_selection(position : vec2<f32>, colour : vec4<f32>) -> vec4<f32> {
+  let low = min(selection.xy, selection.zw);
+  let high = max(selection.xy, selection.zw);
+  if low.x == high.x || low.y == high.y || any(position < low) || any(position > high) {
+    return colour;
+  }
+
+  if any(position < low + 1.0) || any(position > high - 1.0) {
+    return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+  }
+  return vec4<f32>(mix(colour.rgb, vec3<f32>(1.0, 1.0, 1.0), 0.25), 1.0);
+}
+
 // builtins are documented here: https://www.w3.org/TR/WGSL/#builtin-values
 @fragment
 fn fragment_main(@builtin(position) position : vec4<f32>) -> @location(0) vec4<f32> {
@@ -43,5 +110,5 @@ fn fragment_main(@builtin(position) position : vec4<f32>) -> @location(0) vec4<f
   let x = u32(position.x);
   let y = u32(position.y);
 
-  return compute_colour(colour_ranges[y * screen_size.x + x]);
+  return overlay_selection(position.xy, compute_colour(colour_ranges[y * screen_size.x + x]));
 }
\ No newline at end of file
@@ -0,0 +1,501 @@
+/*!
+Scene files, which describe everything needed to reproduce an image:
+
+```toml
+version = 1
+
+[view]
+real = "-0.743643887037"
+imaginary = "0.131825904205"
+zoom = 2e9
+
+[formula]
+name = "newton"
+exponent = 2.0
+polynomial = "1 0 0 -1"
+relaxation = "1"
+phoenix-parameter = "-0.5"
+
+[iteration]
+limit = 65536
+
+[colouring]
+algorithm = "histogram"
+palette = "fire"
+
+[output]
+width = 1920
+height = 1080
+supersampling = 2
+```
+
+Only `version` is required. Settings that are left out keep the values that they'd have without
+a scene file. `formula.file` names the WGSL file for the `custom` formula, relative to the scene
+file. Complex numbers and polynomials are written in the same way as their environment variables.
+
+Images saved by this program have their scene embedded in an `iTXt` chunk (see [`PNG_KEYWORD`]),
+and [`load`] reads it back from them, so that any of them can be rendered again.
+*/
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use dashu_float::DBig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    colour::{Colouring, Palette},
+    formula::{Formula, Polynomial},
+    pixel::Complex,
+    screen,
+    view::View,
+};
+
+/// The version of the format that's written, and the newest that can be read.
+pub const VERSION: u32 = 1;
+
+/// The keyword of the PNG `iTXt` chunk that holds a scene, written in the same way as a file.
+pub const PNG_KEYWORD: &str = "wgpu-mandelbrot-scene";
+
+/// The settings in a scene file, each of which is `None` if it's left out.
+#[derive(Clone, Debug, Default)]
+pub struct Scene {
+    pub view: Option<View>,
+    pub formula: Option<Formula>,
+    /// The WGSL file for [`Formula::Custom`].
+    pub formula_file: Option<PathBuf>,
+    pub exponent: Option<f32>,
+    pub polynomial: Option<Polynomial>,
+    pub relaxation: Option<Complex>,
+    pub phoenix_parameter: Option<Complex>,
+    pub iteration_limit: Option<u32>,
+    pub colouring: Option<Colouring>,
+    pub palette: Option<Palette>,
+    /// The size of the image, after supersampling.
+    pub size: Option<screen::Size>,
+    /// How many pixels are computed along each side of a pixel of the image, and averaged.
+    pub supersampling: Option<u32>,
+}
+
+/// A [`Scene`], as it's written in a file.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    view: Option<ViewTable>,
+    #[serde(default)]
+    formula: FormulaTable,
+    #[serde(default)]
+    iteration: IterationTable,
+    #[serde(default)]
+    colouring: ColouringTable,
+    #[serde(default)]
+    output: OutputTable,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ViewTable {
+    real: String,
+    imaginary: String,
+    zoom: f64,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct FormulaTable {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exponent: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    polynomial: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    relaxation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    phoenix_parameter: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IterationTable {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<u32>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColouringTable {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    algorithm: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    palette: Option<String>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OutputTable {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    supersampling: Option<u32>,
+}
+
+/// Parse `value`, if it's there, describing what's wrong with it as `name` if it's invalid.
+fn parse_field<A: FromStr>(name: &str, value: Option<String>) -> Result<Option<A>, String>
+where
+    A::Err: Display,
+{
+    value
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|err| format!("invalid {}: {}", name, err))
+        })
+        .transpose()
+}
+
+impl Scene {
+    /// `directory` is where the file was read from, which `formula.file` is relative to.
+    fn from_file(file: SceneFile, directory: &Path) -> Result<Self, String> {
+        if file.version == 0 {
+            return Err("invalid version: 0 (versions start at 1)".to_string());
+        }
+        if file.version > VERSION {
+            return Err(format!(
+                "version {} is newer than this program understands (version {})",
+                file.version, VERSION
+            ));
+        }
+
+        let view = file
+            .view
+            .map(|view| {
+                let real = DBig::from_str(&view.real)
+                    .map_err(|_| format!("invalid view.real: {:?}", view.real))?;
+                let imaginary = DBig::from_str(&view.imaginary)
+                    .map_err(|_| format!("invalid view.imaginary: {:?}", view.imaginary))?;
+                if !(view.zoom.is_finite() && view.zoom > 0.0) {
+                    return Err(format!(
+                        "invalid view.zoom: {} (must be positive)",
+                        view.zoom
+                    ));
+                }
+                Ok(View::new(real, imaginary, view.zoom))
+            })
+            .transpose()?;
+
+        if let Some(exponent) = file
+            .formula
+            .exponent
+            .filter(|exponent| !(exponent.is_finite() && *exponent >= 1.0))
+        {
+            return Err(format!(
+                "invalid formula.exponent: {} (must be at least 1)",
+                exponent
+            ));
+        }
+
+        if file.iteration.limit == Some(0) {
+            return Err("invalid iteration.limit: 0 (must be positive)".to_string());
+        }
+
+        let size = match (file.output.width, file.output.height) {
+            (Some(width), Some(height)) if width > 0 && height > 0 => {
+                Some(screen::Size { width, height })
+            }
+            (None, None) => None,
+            _ => return Err(
+                "invalid output: `width` and `height` must both be positive, or both be left out"
+                    .to_string(),
+            ),
+        };
+        if file.output.supersampling == Some(0) {
+            return Err("invalid output.supersampling: 0 (must be positive)".to_string());
+        }
+
+        Ok(Scene {
+            view,
+            formula: parse_field("formula.name", file.formula.name)?,
+            formula_file: file.formula.file.map(|path| directory.join(path)),
+            exponent: file.formula.exponent,
+            polynomial: parse_field("formula.polynomial", file.formula.polynomial)?,
+            relaxation: parse_field("formula.relaxation", file.formula.relaxation)?,
+            phoenix_parameter: parse_field(
+                "formula.phoenix-parameter",
+                file.formula.phoenix_parameter,
+            )?,
+            iteration_limit: file.iteration.limit,
+            colouring: parse_field("colouring.algorithm", file.colouring.algorithm)?,
+            palette: parse_field("colouring.palette", file.colouring.palette)?,
+            size,
+            supersampling: file.output.supersampling,
+        })
+    }
+
+    fn to_file(&self) -> SceneFile {
+        SceneFile {
+            version: VERSION,
+            view: self.view.as_ref().map(|view| ViewTable {
+                real: view.real().to_string(),
+                imaginary: view.imaginary().to_string(),
+                zoom: view.zoom(),
+            }),
+            formula: FormulaTable {
+                name: self.formula.map(|formula| formula.name().to_string()),
+                file: self.formula_file.clone(),
+                exponent: self.exponent,
+                polynomial: self.polynomial.as_ref().map(Polynomial::to_string),
+                relaxation: self.relaxation.map(|relaxation| relaxation.to_string()),
+                phoenix_parameter: self
+                    .phoenix_parameter
+                    .map(|phoenix_parameter| phoenix_parameter.to_string()),
+            },
+            iteration: IterationTable {
+                limit: self.iteration_limit,
+            },
+            colouring: ColouringTable {
+                algorithm: self.colouring.map(|colouring| colouring.name().to_string()),
+                palette: self.palette.map(|palette| palette.name().to_string()),
+            },
+            output: OutputTable {
+                width: self.size.map(|size| size.width),
+                height: self.size.map(|size| size.height),
+                supersampling: self.supersampling,
+            },
+        }
+    }
+}
+
+/**
+Read the scene file at `path`.
+
+Files ending in `.png` are read as images saved by this program, from their [`PNG_KEYWORD`]
+chunk.
+*/
+pub fn load(path: impl AsRef<Path>) -> Result<Scene, Error> {
+    let path = path.as_ref().to_path_buf();
+
+    let is_png = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+    let source = if is_png {
+        read_png(&path)?
+    } else {
+        std::fs::read_to_string(&path).map_err(|err| Error::Io {
+            path: path.clone(),
+            err,
+        })?
+    };
+    let file: SceneFile = toml::from_str(&source).map_err(|err| Error::Toml {
+        path: path.clone(),
+        err,
+    })?;
+
+    let directory = path.parent().unwrap_or(Path::new(""));
+    Scene::from_file(file, directory).map_err(|reason| Error::Invalid {
+        path: path.clone(),
+        reason,
+    })
+}
+
+/// The text of the [`PNG_KEYWORD`] chunk in the image at `path`.
+fn read_png(path: &Path) -> Result<String, Error> {
+    let png_error = |err| Error::Png {
+        path: path.to_path_buf(),
+        err,
+    };
+
+    let file = File::open(path).map_err(|err| Error::Io {
+        path: path.to_path_buf(),
+        err,
+    })?;
+    // Text chunks before the image data are read along with the header.
+    let reader = png::Decoder::new(BufReader::new(file))
+        .read_info()
+        .map_err(png_error)?;
+
+    let chunk = reader
+        .info()
+        .utf8_text
+        .iter()
+        .find(|chunk| chunk.keyword == PNG_KEYWORD)
+        .ok_or_else(|| Error::MissingFromPng(path.to_path_buf()))?;
+    chunk.get_text().map_err(png_error)
+}
+
+/// `scene`, written in the same way as a file.
+pub fn write(scene: &Scene) -> Result<String, Error> {
+    toml::to_string(&scene.to_file()).map_err(Error::Serialize)
+}
+
+/// Write `scene` to the file at `path`, replacing it if it exists.
+pub fn save(path: impl AsRef<Path>, scene: &Scene) -> Result<(), Error> {
+    let path = path.as_ref().to_path_buf();
+
+    let text = write(scene)?;
+    std::fs::write(&path, text).map_err(|err| Error::Io { path, err })
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io {
+        path: PathBuf,
+        err: std::io::Error,
+    },
+    Toml {
+        path: PathBuf,
+        err: toml::de::Error,
+    },
+    Invalid {
+        path: PathBuf,
+        reason: String,
+    },
+    Serialize(toml::ser::Error),
+    Png {
+        path: PathBuf,
+        err: png::DecodingError,
+    },
+    MissingFromPng(PathBuf),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io { path, err } => write!(f, "failed to access {}: {}", path.display(), err),
+            Error::Toml { path, err } => write!(f, "invalid scene in {}: {}", path.display(), err),
+            Error::Invalid { path, reason } => {
+                write!(f, "invalid scene in {}: {}", path.display(), reason)
+            }
+            Error::Serialize(err) => write!(f, "failed to write scene: {}", err),
+            Error::Png { path, err } => write!(f, "failed to read {}: {}", path.display(), err),
+            Error::MissingFromPng(path) => write!(
+                f,
+                "{} has no scene in it (only images saved by wgpu-mandelbrot do)",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        screenshot::Image,
+        test_support::{self, TemporaryFile},
+    };
+
+    fn full_scene() -> Scene {
+        Scene {
+            view: Some(
+                "-0.74364388703715870475219150 0.13182590420531197049 2e20"
+                    .parse()
+                    .unwrap(),
+            ),
+            formula: Some(Formula::Custom),
+            formula_file: Some(PathBuf::from("formula.wgsl")),
+            exponent: Some(3.5),
+            polynomial: Some("1 0 0 -1".parse().unwrap()),
+            relaxation: Some("1+0.5i".parse().unwrap()),
+            phoenix_parameter: Some("-0.5".parse().unwrap()),
+            iteration_limit: Some(4096),
+            colouring: Some(Colouring::Histogram),
+            palette: Some(Palette::Ocean),
+            size: Some(screen::Size {
+                width: 1920,
+                height: 1080,
+            }),
+            supersampling: Some(2),
+        }
+    }
+
+    /// Save a 1x1 black image at `path`, with `text_chunks`.
+    fn save_image(path: &Path, text_chunks: &[(&str, String)]) {
+        let image = Image {
+            size: screen::Size {
+                width: 1,
+                height: 1,
+            },
+            data: vec![0, 0, 0, 255],
+        };
+        image.save(path, text_chunks).unwrap();
+    }
+
+    #[test]
+    fn saved_scenes_load_back() {
+        for (name, scene) in [("full", full_scene()), ("empty", Scene::default())] {
+            let file = TemporaryFile::new("scene", &format!("{}.toml", name));
+            save(file.path(), &scene).unwrap();
+
+            let image = TemporaryFile::new("scene", &format!("{}.png", name));
+            save_image(image.path(), &[(PNG_KEYWORD, write(&scene).unwrap())]);
+
+            for path in [file.path(), image.path()] {
+                let loaded = load(path).unwrap();
+                assert_eq!(loaded.view, scene.view);
+                assert_eq!(loaded.formula, scene.formula);
+                assert_eq!(
+                    loaded.formula_file,
+                    scene
+                        .formula_file
+                        .as_ref()
+                        .map(|file| std::env::temp_dir().join(file))
+                );
+                assert_eq!(loaded.exponent, scene.exponent);
+                assert_eq!(loaded.polynomial, scene.polynomial);
+                assert_eq!(loaded.relaxation, scene.relaxation);
+                assert_eq!(loaded.phoenix_parameter, scene.phoenix_parameter);
+                assert_eq!(loaded.iteration_limit, scene.iteration_limit);
+                assert_eq!(loaded.colouring, scene.colouring);
+                assert_eq!(loaded.palette, scene.palette);
+                assert_eq!(loaded.size, scene.size);
+                assert_eq!(loaded.supersampling, scene.supersampling);
+            }
+        }
+    }
+
+    #[test]
+    fn images_without_a_scene_are_rejected() {
+        let image = TemporaryFile::new("scene", "no-scene.png");
+        save_image(image.path(), &[]);
+        assert!(matches!(load(image.path()), Err(Error::MissingFromPng(_))));
+    }
+
+    #[test]
+    fn invalid_scenes_are_rejected() {
+        let view = "[view]\nreal = \"0\"\nimaginary = \"0\"\nzoom =";
+        let sources = [
+            "version = 0".to_string(),
+            "version = 2".to_string(),
+            format!("version = 1\n{} 0.0", view),
+            format!("version = 1\n{} -1.0", view),
+            "version = 1\n[formula]\nexponent = 0.5".to_string(),
+            "version = 1\n[formula]\nexponent = nan".to_string(),
+            "version = 1\n[formula]\nexponent = inf".to_string(),
+            "version = 1\n[iteration]\nlimit = 0".to_string(),
+            "version = 1\n[output]\nwidth = 0\nheight = 1080".to_string(),
+            "version = 1\n[output]\nwidth = 1920".to_string(),
+            "version = 1\n[output]\nsupersampling = 0".to_string(),
+            "version = 1\n[colouring]\npalette = \"plaid\"".to_string(),
+        ];
+        test_support::assert_rejected(
+            &TemporaryFile::new("scene", "invalid.toml"),
+            sources,
+            |path| load(path),
+            |err| matches!(err, Error::Invalid { .. }),
+        );
+    }
+}
@@ -1,11 +1,101 @@
 //! Screen data.
 
+use std::{fmt::Display, str::FromStr};
+
 use bytemuck::{Pod, Zeroable};
 
 /// [`bytemuck`]-compatible screen size.
 #[repr(C)]
-#[derive(Pod, Zeroable, Clone, Copy, Debug)]
+#[derive(Pod, Zeroable, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Size {
     pub width: u32,
     pub height: u32,
 }
+
+/// Formats as `<width>x<height>`, which [`Size::from_str`] reads back.
+impl Display for Size {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// The largest width or height that [`Size::from_str`] accepts, so that `width * height` fits in a
+/// `u32`.
+pub const MAX_SIDE: u32 = u16::MAX as u32;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseSizeError(String);
+
+impl Display for ParseSizeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid size: {:?} (expected `<width>x<height>`, each from 1 to {})",
+            self.0, MAX_SIDE
+        )
+    }
+}
+
+impl std::error::Error for ParseSizeError {}
+
+/// Parses `<width>x<height>`, where both are from 1 to [`MAX_SIDE`].
+impl FromStr for Size {
+    type Err = ParseSizeError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let error = || ParseSizeError(input.to_string());
+
+        let (width, height) = input.split_once('x').ok_or_else(error)?;
+        let width = width.parse::<u32>().map_err(|_| error())?;
+        let height = height.parse::<u32>().map_err(|_| error())?;
+        if !(1..=MAX_SIDE).contains(&width) || !(1..=MAX_SIDE).contains(&height) {
+            return Err(error());
+        }
+
+        Ok(Size { width, height })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_parse_back() {
+        let size = Size {
+            width: 1920,
+            height: 1080,
+        };
+        assert_eq!(size.to_string().parse(), Ok(size));
+
+        let size = Size {
+            width: MAX_SIDE,
+            height: MAX_SIDE,
+        };
+        assert_eq!(size.to_string().parse(), Ok(size));
+    }
+
+    #[test]
+    fn invalid_sizes_are_rejected() {
+        for input in [
+            "",
+            "1920",
+            "1920x",
+            "x1080",
+            "0x1080",
+            "1920x0",
+            "65536x1080",
+            "1920x65536",
+            "70000x70000",
+            "-1x1",
+            "1920 x 1080",
+        ] {
+            assert_eq!(
+                input.parse::<Size>(),
+                Err(ParseSizeError(input.to_string())),
+                "{:?}",
+                input
+            );
+        }
+    }
+}
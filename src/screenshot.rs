@@ -0,0 +1,265 @@
+/*!
+Saving what's on screen as a PNG.
+
+The surface texture can't be read back, so the frame is drawn again into a texture that can.
+*/
+
+use std::{
+    fmt::Display,
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use crate::{command_buffer, command_encoder::CommandEncoderExt, screen};
+
+/// Written to the `Software` chunk of every image.
+const SOFTWARE: &str = concat!("wgpu-mandelbrot ", env!("CARGO_PKG_VERSION"));
+
+/// An 8-bit RGBA image, with rows from top to bottom.
+pub struct Image {
+    pub size: screen::Size,
+    pub data: Vec<u8>,
+}
+
+/**
+Draw a frame of `size` with `render_pipeline` into a texture of `format`, and read it back.
+
+`bind_groups` are set in order, and the screen is drawn in the same way as `render.wgsl#vertex_main`
+expects. Only 8-bit RGBA and BGRA formats are supported, which covers the surface formats that
+`wgpu` prefers.
+*/
+pub fn capture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    size: screen::Size,
+    format: wgpu::TextureFormat,
+    render_pipeline: &wgpu::RenderPipeline,
+    bind_groups: &[&wgpu::BindGroup],
+) -> Result<Image, Error> {
+    let swap_red_and_blue = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => return Err(Error::UnsupportedFormat(format)),
+    };
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("screenshot-texture"),
+        size: wgpu::Extent3d {
+            width: size.width,
+            height: size.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+    });
+    let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+    // Rows of a texture copy must be aligned.
+    let unpadded_bytes_per_row = size.width * 4;
+    let bytes_per_row = unpadded_bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+        * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("screenshot-buffer"),
+        size: bytes_per_row as u64 * size.height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let command_buffer = command_buffer::create(
+        device,
+        &wgpu::CommandEncoderDescriptor::default(),
+        |command_encoder| {
+            command_encoder.with_render_pass(
+                &wgpu::RenderPassDescriptor {
+                    label: Some("screenshot-render-pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &texture_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: true,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                },
+                |render_pass| {
+                    render_pass.set_pipeline(render_pipeline);
+                    for (index, bind_group) in bind_groups.iter().enumerate() {
+                        render_pass.set_bind_group(index as u32, bind_group, &[]);
+                    }
+                    render_pass.draw(0..4, 0..1);
+                },
+            );
+
+            command_encoder.copy_texture_to_buffer(
+                texture.as_image_copy(),
+                wgpu::ImageCopyBuffer {
+                    buffer: &buffer,
+                    layout: wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: std::num::NonZeroU32::new(bytes_per_row),
+                        rows_per_image: None,
+                    },
+                },
+                wgpu::Extent3d {
+                    width: size.width,
+                    height: size.height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        },
+    );
+    queue.submit([command_buffer]);
+
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, |map_result| {
+        map_result.unwrap_or_else(|err| panic!("buffer async error: {}", err))
+    });
+    device.poll(wgpu::Maintain::Wait);
+
+    let mut data = Vec::with_capacity((unpadded_bytes_per_row * size.height) as usize);
+    for row in slice.get_mapped_range().chunks(bytes_per_row as usize) {
+        data.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+    }
+    buffer.unmap();
+    buffer.destroy();
+    texture.destroy();
+
+    if swap_red_and_blue {
+        for pixel in data.chunks_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    Ok(Image { size, data })
+}
+
+/// Convert an sRGB-encoded channel to linear light, from `0` to `1`.
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// The inverse of [`srgb_to_linear`].
+fn linear_to_srgb(value: f32) -> u8 {
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (value * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+impl Image {
+    /**
+    Shrink the image by `factor` along each side, averaging each `factor` by `factor` block of
+    pixels into one.
+
+    The image's width and height must be multiples of `factor`. Colours are averaged in linear
+    light, so that edges don't come out too dark.
+    */
+    pub fn downsample(&self, factor: u32) -> Image {
+        debug_assert!(
+            self.size.width.is_multiple_of(factor) && self.size.height.is_multiple_of(factor)
+        );
+        let size = screen::Size {
+            width: self.size.width / factor,
+            height: self.size.height / factor,
+        };
+        let samples = (factor * factor) as f32;
+
+        let mut data = Vec::with_capacity((size.width * size.height * 4) as usize);
+        for y in 0..size.height {
+            for x in 0..size.width {
+                let mut sum = [0.0; 4];
+                for source_y in y * factor..(y + 1) * factor {
+                    for source_x in x * factor..(x + 1) * factor {
+                        let index = ((source_y * self.size.width + source_x) * 4) as usize;
+                        for (channel, sum) in sum.iter_mut().enumerate().take(3) {
+                            *sum += srgb_to_linear(self.data[index + channel]);
+                        }
+                        // Alpha isn't gamma encoded.
+                        sum[3] += self.data[index + 3] as f32;
+                    }
+                }
+                data.extend(sum[..3].iter().map(|sum| linear_to_srgb(sum / samples)));
+                data.push((sum[3] / samples).round() as u8);
+            }
+        }
+
+        Image { size, data }
+    }
+
+    /**
+    Save the image as a PNG at `path`.
+
+    Each of `text` is written as an `iTXt` chunk of a keyword and its text, before the image data
+    so that it can be read without decoding the image.
+    */
+    pub fn save(&self, path: impl AsRef<Path>, text: &[(&str, String)]) -> Result<(), Error> {
+        let path = path.as_ref();
+        let png_error = |err| Error::Png {
+            path: path.to_path_buf(),
+            err,
+        };
+
+        let file = File::create(path).map_err(|err| Error::Io {
+            path: path.to_path_buf(),
+            err,
+        })?;
+
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(file), self.size.width, self.size.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        encoder
+            .add_text_chunk("Software".to_string(), SOFTWARE.to_string())
+            .map_err(png_error)?;
+        for (keyword, text) in text {
+            encoder
+                .add_itxt_chunk(keyword.to_string(), text.clone())
+                .map_err(png_error)?;
+        }
+
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        writer.write_image_data(&self.data).map_err(png_error)?;
+        writer.finish().map_err(png_error)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    UnsupportedFormat(wgpu::TextureFormat),
+    Io {
+        path: PathBuf,
+        err: std::io::Error,
+    },
+    Png {
+        path: PathBuf,
+        err: png::EncodingError,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnsupportedFormat(format) => {
+                write!(f, "can't take screenshots of {:?} surfaces", format)
+            }
+            Error::Io { path, err } => write!(f, "failed to create {}: {}", path.display(), err),
+            Error::Png { path, err } => write!(f, "failed to write {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,131 @@
+/*!
+Render progress, for the window title and the terminal's status line.
+
+A view is converged once every pixel has escaped or reached the iteration limit, after which
+nothing more is computed until something changes.
+
+The status line is the last line of the terminal, which is rewritten without a newline, so it
+has to be cleared with [`clear_line`] before anything else is printed to stderr.
+*/
+
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crate::view::{self, View};
+
+/// How far the current view has been rendered.
+pub struct Progress<'a> {
+    pub view: &'a View,
+    pub escaped_pixels: usize,
+    /// Pixels that reached the iteration limit without escaping.
+    pub limited_pixels: usize,
+    pub total_pixels: usize,
+    /// How long it's been since the view last changed.
+    pub elapsed: Duration,
+}
+
+impl Progress<'_> {
+    pub fn is_converged(&self) -> bool {
+        self.escaped_pixels + self.limited_pixels >= self.total_pixels
+    }
+
+    /// The fraction of the pixels that have escaped, from `0` to `1`.
+    pub fn escaped_fraction(&self) -> f64 {
+        if self.total_pixels == 0 {
+            return 1.0;
+        }
+        self.escaped_pixels as f64 / self.total_pixels as f64
+    }
+
+    /// The window title, which is [`Progress`]'s `Display` after the program's name.
+    pub fn title(&self) -> String {
+        format!("wgpu-mandelbrot - {}", self)
+    }
+}
+
+/**
+Formats as `<real> <imaginary> at zoom <zoom> - <escaped>% escaped, <state> after <elapsed>`.
+
+The center is rounded to the digits needed to locate pixels at the zoom (see
+[`view::decimal_precision`]), rather than every digit that was typed in or panned to.
+*/
+impl Display for Progress<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = view::decimal_precision(self.view.zoom());
+        write!(
+            f,
+            "{} {} at zoom {:e} - {:.1}% escaped, {} after {:.1}s",
+            self.view.real().clone().with_precision(digits).value(),
+            self.view.imaginary().clone().with_precision(digits).value(),
+            self.view.zoom(),
+            100.0 * self.escaped_fraction(),
+            if self.is_converged() {
+                "converged"
+            } else {
+                "rendering"
+            },
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+/// Whether the status line has been written, and not cleared since.
+static LINE_SHOWN: AtomicBool = AtomicBool::new(false);
+
+/// Rewrite the status line on stderr with `progress`.
+pub fn show_line(progress: &Progress) {
+    // Overwrite the last status, and clear whatever's left of it.
+    eprint!("\r{}\x1b[K", progress);
+    LINE_SHOWN.store(true, Ordering::Relaxed);
+}
+
+/// Clear the status line, if it's shown, so that whatever's printed next starts on an empty line.
+pub fn clear_line() {
+    if LINE_SHOWN.swap(false, Ordering::Relaxed) {
+        eprint!("\r\x1b[K");
+    }
+}
+
+/// End the status line, if it's shown, leaving it on screen above whatever's printed next.
+pub fn finish_line() {
+    if LINE_SHOWN.swap(false, Ordering::Relaxed) {
+        eprintln!();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_center_is_rounded_to_the_zoom() {
+        let mut progress = Progress {
+            view: &"-0.74364388703715870475219150611477 0.13182590420531197049313205638514 1"
+                .parse()
+                .unwrap(),
+            escaped_pixels: 1,
+            limited_pixels: 1,
+            total_pixels: 4,
+            elapsed: Duration::from_millis(1500),
+        };
+        assert_eq!(
+            progress.to_string(),
+            "-0.74364388703716 0.13182590420531 at zoom 1e0 - 25.0% escaped, rendering after 1.5s"
+        );
+
+        let deeper = "-0.74364388703715870475219150611477 0.13182590420531197049313205638514 1e10"
+            .parse()
+            .unwrap();
+        progress.view = &deeper;
+        assert!(
+            progress.to_string().starts_with(
+                "-0.743643887037158704752192 0.131825904205311970493132 at zoom 1e10 "
+            ),
+            "{}",
+            progress
+        );
+    }
+}
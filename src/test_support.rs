@@ -0,0 +1,52 @@
+//! Helpers shared by tests in different modules.
+
+use std::{
+    fmt::Debug,
+    path::{Path, PathBuf},
+};
+
+/// A file in the temporary directory that no other test uses, which is removed when it's created
+/// and when it's dropped.
+pub struct TemporaryFile(PathBuf);
+
+impl TemporaryFile {
+    /// `module` and `name` must be unique among the tests that use temporary files.
+    pub fn new(module: &str, name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "wgpu-mandelbrot-{}-{}-{}",
+            module,
+            std::process::id(),
+            name
+        ));
+        let _ = std::fs::remove_file(&path);
+        TemporaryFile(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TemporaryFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Write each of `sources` to `file`, and check that `load` fails on it with an error that
+/// `is_expected`.
+pub fn assert_rejected<T, E: Debug>(
+    file: &TemporaryFile,
+    sources: impl IntoIterator<Item = impl AsRef<str>>,
+    load: impl Fn(&Path) -> Result<T, E>,
+    is_expected: impl Fn(&E) -> bool,
+) {
+    for source in sources {
+        let source = source.as_ref();
+        std::fs::write(file.path(), source).unwrap();
+        match load(file.path()) {
+            Ok(_) => panic!("accepted {:?}", source),
+            Err(err) => assert!(is_expected(&err), "{:?} from {:?}", err, source),
+        }
+    }
+}
@@ -0,0 +1,261 @@
+/*!
+Animated transitions between views.
+
+Zooming is interpolated logarithmically, so that each factor of ten takes the same time. The
+center moves at a constant speed across the screen, rather than across the complex plane, so
+that it doesn't rush past while zoomed out and crawl while zoomed in.
+
+Views that are many screens apart are reached by zooming out until both are on screen, and then
+zooming back in, rather than sliding across every screen in between.
+*/
+
+use std::time::{Duration, Instant};
+
+use crate::{colour::ColourRange, screen, view::View};
+
+/// How long a transition takes when the zoom doesn't change.
+const BASE_DURATION: Duration = Duration::from_millis(500);
+
+/// How much longer a transition takes for each factor of ten that the zoom changes by.
+const DURATION_PER_DECADE: Duration = Duration::from_millis(100);
+
+const MAX_DURATION: Duration = Duration::from_secs(3);
+
+/// How many screens apart two views can be, at the shallower of their zooms, before the
+/// transition between them zooms out and back in.
+const MAX_SCREENS_APART: f64 = 4.0;
+
+/// An animation from one view to another.
+pub struct Transition {
+    /// One leg, or two when zooming out and back in.
+    legs: Vec<Leg>,
+    start: Instant,
+    /// The view that was last returned by [`Transition::view_at`].
+    current: View,
+    iterations: u32,
+}
+
+impl Transition {
+    /**
+    Start animating from `from` to `to`.
+
+    `iterations` is how many iterations the pixels on screen have been through, which is how long
+    the colours carried along by [`warp`] take to be replaced once the transition is over.
+    */
+    pub fn new(from: View, to: View, iterations: u32) -> Self {
+        let (real, imaginary) = from.offset_to(&to);
+        let distance = real.hypot(imaginary);
+        // The width of the screen is `2 * scale`.
+        let screens_apart = distance / (2.0 * from.scale().max(to.scale()));
+
+        let legs = if screens_apart > MAX_SCREENS_APART {
+            // Halfway between, zoomed out until they're half a screen apart.
+            let mut middle = from.clone();
+            middle.set_zoom(from.zoom().min(to.zoom()) * 0.5 / screens_apart);
+            middle.translate(real / 2.0, imaginary / 2.0);
+            vec![Leg::new(from.clone(), middle.clone()), Leg::new(middle, to)]
+        } else {
+            vec![Leg::new(from.clone(), to)]
+        };
+
+        Transition {
+            legs,
+            start: Instant::now(),
+            current: from,
+            iterations,
+        }
+    }
+
+    /// The view that was last returned by [`Transition::view_at`], or the starting view.
+    pub fn current(&self) -> &View {
+        &self.current
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    fn duration(&self) -> Duration {
+        self.legs.iter().map(|leg| leg.duration).sum()
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        now.duration_since(self.start) >= self.duration()
+    }
+
+    /// The view to show at `now`.
+    pub fn view_at(&mut self, now: Instant) -> View {
+        let mut elapsed = now.duration_since(self.start);
+        let last = self.legs.len() - 1;
+        for (index, leg) in self.legs.iter().enumerate() {
+            if elapsed < leg.duration || index == last {
+                let t = (elapsed.as_secs_f64() / leg.duration.as_secs_f64()).clamp(0.0, 1.0);
+                self.current = leg.view_at(t);
+                break;
+            }
+            elapsed -= leg.duration;
+        }
+        self.current.clone()
+    }
+}
+
+/// Part of a [`Transition`], which zooms in or out while moving the center.
+struct Leg {
+    from: View,
+    to: View,
+    duration: Duration,
+    /// `ln(to.zoom() / from.zoom())`.
+    log_zoom_ratio: f64,
+    /// The offset from `from`'s center to `to`'s, as `(real, imaginary)`.
+    offset: (f64, f64),
+}
+
+impl Leg {
+    fn new(from: View, to: View) -> Self {
+        let log_zoom_ratio = to.zoom().ln() - from.zoom().ln();
+        let decades = (log_zoom_ratio / std::f64::consts::LN_10).abs();
+        let duration = (BASE_DURATION + DURATION_PER_DECADE.mul_f64(decades)).min(MAX_DURATION);
+
+        Leg {
+            offset: from.offset_to(&to),
+            from,
+            to,
+            duration,
+            log_zoom_ratio,
+        }
+    }
+
+    /// The view at `t`, from `0` to `1`.
+    fn view_at(&self, t: f64) -> View {
+        if t == 1.0 {
+            return self.to.clone();
+        }
+
+        // Ease in and out.
+        let t = t * t * (3.0 - 2.0 * t);
+        let fraction = center_fraction(t, self.log_zoom_ratio);
+
+        // Move from whichever end is closer, so that the center is as precise as the zoom needs
+        // at both ends.
+        let mut view;
+        let fraction_from = if fraction < 0.5 {
+            view = self.from.clone();
+            fraction
+        } else {
+            view = self.to.clone();
+            fraction - 1.0
+        };
+        view.set_zoom(
+            (self.from.zoom().ln() + t * self.log_zoom_ratio)
+                .exp()
+                .clamp(f64::MIN_POSITIVE, f64::MAX),
+        );
+        view.translate(self.offset.0 * fraction_from, self.offset.1 * fraction_from);
+        view
+    }
+}
+
+/**
+How far the center has moved towards the end of a [`Leg`] at `t`, from `0` to `1`, where
+`log_zoom_ratio` is [`Leg::log_zoom_ratio`].
+
+The center's speed across the screen is constant when its speed across the complex plane is
+proportional to the scale, `e^(-t * log_zoom_ratio)`. This is the integral of that from `0` to `t`,
+over the integral from `0` to `1`.
+*/
+fn center_fraction(t: f64, log_zoom_ratio: f64) -> f64 {
+    let ratio = log_zoom_ratio;
+    if ratio.abs() < 1e-9 {
+        t
+    } else if ratio > 0.0 {
+        (-t * ratio).exp_m1() / (-ratio).exp_m1()
+    } else {
+        // The same, rearranged so that nothing overflows when zooming out a long way.
+        ((t - 1.0) * -ratio).exp() * (t * ratio).exp_m1() / ratio.exp_m1()
+    }
+}
+
+/// Move `colour_ranges`, which show `from` on a screen of `size`, to where they are in `to`.
+/// Pixels that weren't on screen are uncoloured.
+pub fn warp(from: &View, to: &View, size: screen::Size, colour_ranges: &mut [ColourRange]) {
+    let old_colour_ranges = colour_ranges.to_vec();
+    let (center_real, center_imaginary) = from.offset_to(to);
+
+    for y in 0..size.height {
+        for x in 0..size.width {
+            let (real, imaginary) = to.offset_at(x as f64 + 0.5, y as f64 + 0.5, size);
+            let (old_x, old_y) =
+                from.position_of(center_real + real, center_imaginary + imaginary, size);
+
+            colour_ranges[(y * size.width + x) as usize] = if (0.0..size.width as f64)
+                .contains(&old_x)
+                && (0.0..size.height as f64).contains(&old_y)
+            {
+                old_colour_ranges[old_y as usize * size.width as usize + old_x as usize]
+            } else {
+                ColourRange::default()
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn center_fraction_is_monotonic_from_0_to_1() {
+        for ratio in [1e-12, -1e-12, 1.0, -1.0, 700.0, -700.0] {
+            assert_eq!(center_fraction(0.0, ratio), 0.0, "ratio {}", ratio);
+            assert_eq!(center_fraction(1.0, ratio), 1.0, "ratio {}", ratio);
+
+            let mut previous = 0.0;
+            for step in 0..=1000 {
+                let fraction = center_fraction(step as f64 / 1000.0, ratio);
+                assert!(fraction.is_finite(), "ratio {} at step {}", ratio, step);
+                assert!(fraction >= previous, "ratio {} at step {}", ratio, step);
+                previous = fraction;
+            }
+        }
+    }
+
+    #[test]
+    fn center_moves_sooner_when_zooming_in() {
+        // Zooming in, the screen covers less of the plane as time goes on, so the center has
+        // covered more than half the distance by halfway; zooming out, less.
+        assert!(center_fraction(0.5, 1.0) > 0.5);
+        assert!(center_fraction(0.5, -1.0) < 0.5);
+    }
+
+    #[test]
+    fn nearby_views_are_one_leg() {
+        let from: View = "0 0 1".parse().unwrap();
+        let to: View = "1 0 1e3".parse().unwrap();
+        let mut transition = Transition::new(from, to.clone(), 0);
+        assert_eq!(transition.legs.len(), 1);
+
+        let end = transition.start + transition.duration();
+        assert!(transition.is_finished(end));
+        assert_eq!(transition.view_at(end), to);
+    }
+
+    #[test]
+    fn distant_deep_views_zoom_out_and_back_in() {
+        let from: View = "-0.75 0.1 1e30".parse().unwrap();
+        let to: View = "-0.75 0.1001 1e31".parse().unwrap();
+        let mut transition = Transition::new(from.clone(), to.clone(), 0);
+        assert_eq!(transition.legs.len(), 2);
+
+        // Both views are on screen in the middle.
+        let middle = &transition.legs[0].to;
+        let (real, imaginary) = middle.offset_to(&to);
+        assert!(real.abs().max(imaginary.abs()) < middle.scale());
+        let (real, imaginary) = middle.offset_to(&from);
+        assert!(real.abs().max(imaginary.abs()) < middle.scale());
+
+        let halfway = transition.start + transition.legs[0].duration;
+        assert!(transition.view_at(halfway).zoom() < 1e5);
+        let end = transition.start + transition.duration();
+        assert_eq!(transition.view_at(end), to);
+    }
+}
@@ -37,14 +37,18 @@ impl<A: bytemuck::Pod + bytemuck::Zeroable> Buffer<A> {
         &self.buffer
     }
 
-    pub fn slice<S: RangeBounds<wgpu::BufferAddress>>(&self, bounds: S) -> Slice<A> {
+    pub fn slice<S: RangeBounds<wgpu::BufferAddress>>(&self, bounds: S) -> Slice<'_, A> {
         Slice {
             slice: self.buffer.slice(bounds),
             phantom_data: PhantomData,
         }
     }
 
-    pub fn binding_resource(&self, offset: u64, size: Option<NonZeroU64>) -> wgpu::BindingResource {
+    pub fn binding_resource(
+        &self,
+        offset: u64,
+        size: Option<NonZeroU64>,
+    ) -> wgpu::BindingResource<'_> {
         wgpu::BindingResource::Buffer(wgpu::BufferBinding {
             buffer: &self.buffer,
             offset,
@@ -97,7 +101,7 @@ impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> Deref for View<'a, A> {
     type Target = [A];
 
     fn deref(&self) -> &Self::Target {
-        bytemuck::cast_slice(&*self.view)
+        bytemuck::cast_slice(&self.view)
     }
 }
 
@@ -111,13 +115,13 @@ impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> Deref for ViewMut<'a, A> {
     type Target = [A];
 
     fn deref(&self) -> &Self::Target {
-        bytemuck::cast_slice(&*self.view_mut)
+        bytemuck::cast_slice(&self.view_mut)
     }
 }
 
 impl<'a, A: bytemuck::Pod + bytemuck::Zeroable> DerefMut for ViewMut<'a, A> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        bytemuck::cast_slice_mut(&mut *self.view_mut)
+        bytemuck::cast_slice_mut(&mut self.view_mut)
     }
 }
 
@@ -13,7 +13,7 @@ impl<A: bytemuck::Pod + bytemuck::Zeroable> Var<A> {
         queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(&[contents]));
     }
 
-    pub fn binding_resource(&self) -> wgpu::BindingResource {
+    pub fn binding_resource(&self) -> wgpu::BindingResource<'_> {
         wgpu::BindingResource::Buffer(wgpu::BufferBinding {
             buffer: &self.buffer,
             offset: 0,
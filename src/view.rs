@@ -0,0 +1,309 @@
+/*!
+The region of the complex plane that's on screen.
+*/
+
+use std::{fmt::Display, str::FromStr};
+
+use dashu_float::{DBig, FBig};
+
+use crate::{
+    perturbation::{self, BigComplex},
+    screen,
+};
+
+/**
+The center and zoom of the screen.
+
+The center is stored in decimal, so that a view can be written out and read back in exactly,
+and so that coordinates typed in by hand aren't rounded. Repeatedly moving the center only
+rounds to the precision that the zoom requires (see [`perturbation::precision`]), rather than
+to `f64`. The GPU only gets a [`BigComplex`] rounded from it.
+
+When `zoom = 1.0`, the screen shows `center - (2 + 2i)` to `center + (2 + 2i)`.
+
+Unlike the center, the zoom is an `f64`, so views can't be zoomed in past `f64::MAX` (about
+`1.8e308`), which is where zooming clamps it. [`View::scale`] and [`View::pixel_spacing`] become
+subnormal, and lose precision, shortly before that. Going deeper would need the zoom to be stored
+with a separate exponent, like [`perturbation::Scaled`].
+*/
+#[derive(Clone, Debug, PartialEq)]
+pub struct View {
+    real: DBig,
+    imaginary: DBig,
+    zoom: f64,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View {
+            real: DBig::from_str("-0.74529").unwrap(),
+            imaginary: DBig::from_str("0.113075").unwrap(),
+            zoom: 1.0,
+        }
+    }
+}
+
+impl View {
+    /// The `zoom` must be finite and positive.
+    pub fn new(real: DBig, imaginary: DBig, zoom: f64) -> Self {
+        let mut view = View {
+            real,
+            imaginary,
+            zoom: 1.0,
+        };
+        view.set_zoom(zoom);
+        view
+    }
+
+    /// The real part of the center.
+    pub fn real(&self) -> &DBig {
+        &self.real
+    }
+
+    /// The imaginary part of the center.
+    pub fn imaginary(&self) -> &DBig {
+        &self.imaginary
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    /// The `zoom` must be finite and positive.
+    pub fn set_zoom(&mut self, zoom: f64) {
+        assert!(zoom.is_finite() && zoom > 0.0, "invalid zoom: {}", zoom);
+        self.zoom = zoom;
+    }
+
+    /// Set the zoom, keeping the point at `(x, y)` on a screen of `size` in place.
+    pub fn zoom_at(&mut self, zoom: f64, x: f64, y: f64, size: screen::Size) {
+        let (real, imaginary) = self.offset_at(x, y, size);
+        self.set_zoom(zoom);
+        let (new_real, new_imaginary) = self.offset_at(x, y, size);
+        self.translate(real - new_real, imaginary - new_imaginary);
+    }
+
+    /// Half the width of the screen in the complex plane; see `compute.wgsl#scale`.
+    pub fn scale(&self) -> f64 {
+        2.0 / self.zoom
+    }
+
+    /// The distance between neighbouring pixels on a screen of `size`, in whichever direction
+    /// they're closer together.
+    pub fn pixel_spacing(&self, size: screen::Size) -> f64 {
+        2.0 * self.scale() / size.width.max(size.height).max(1) as f64
+    }
+
+    /// Move the view so that its contents move by `(x, y)` pixels on a screen of `size`.
+    pub fn pan(&mut self, x: f64, y: f64, size: screen::Size) {
+        let scale = self.scale();
+        self.translate(
+            -2.0 * scale * x / size.width as f64,
+            -2.0 * scale * y / size.height as f64,
+        );
+    }
+
+    /**
+    The offset from the center to the point at `(x, y)` on a screen of `size`, as
+    `(real, imaginary)`. `(0, 0)` is the top left corner of the screen.
+
+    Corresponds to the calculation of `c` in `compute.wgsl#mandelbrot`.
+    */
+    pub fn offset_at(&self, x: f64, y: f64, size: screen::Size) -> (f64, f64) {
+        let scale = self.scale();
+        (
+            scale * (2.0 * x / size.width as f64 - 1.0),
+            scale * (2.0 * y / size.height as f64 - 1.0),
+        )
+    }
+
+    /// The inverse of [`View::offset_at`]: the point on a screen of `size` that's `(real, imaginary)`
+    /// from the center.
+    pub fn position_of(&self, real: f64, imaginary: f64, size: screen::Size) -> (f64, f64) {
+        let scale = self.scale();
+        (
+            (real / scale + 1.0) * size.width as f64 / 2.0,
+            (imaginary / scale + 1.0) * size.height as f64 / 2.0,
+        )
+    }
+
+    /// The offset from this view's center to `other`'s, as `(real, imaginary)`.
+    pub fn offset_to(&self, other: &View) -> (f64, f64) {
+        (
+            (&other.real - &self.real).to_f64().value(),
+            (&other.imaginary - &self.imaginary).to_f64().value(),
+        )
+    }
+
+    /// The largest real or imaginary part of any point on screen, ignoring sign.
+    pub fn magnitude(&self) -> f64 {
+        let real = self.real.to_f64().value().abs();
+        let imaginary = self.imaginary.to_f64().value().abs();
+        real.max(imaginary) + self.scale()
+    }
+
+    /// The center, in enough binary precision to locate pixels at the current zoom.
+    pub fn center(&self) -> BigComplex {
+        let digits = decimal_precision(self.zoom);
+        let to_binary = |value: &DBig| -> FBig {
+            value
+                .clone()
+                .with_precision(digits)
+                .value()
+                .to_binary()
+                .value()
+        };
+
+        BigComplex {
+            real: to_binary(&self.real),
+            imaginary: to_binary(&self.imaginary),
+        }
+        .with_precision(perturbation::precision(self.zoom))
+    }
+
+    /**
+    Move the center by `real + imaginary * i`.
+
+    Does nothing if either part isn't finite, such as when panning on a screen with no area.
+    */
+    pub fn translate(&mut self, real: f64, imaginary: f64) {
+        if !(real.is_finite() && imaginary.is_finite()) {
+            return;
+        }
+
+        let digits = decimal_precision(self.zoom);
+        let translate = |value: &DBig, offset: f64| -> DBig {
+            let value = value
+                .clone()
+                .with_precision(value.precision().max(digits))
+                .value();
+            let offset: FBig = FBig::try_from(offset).unwrap();
+            let offset = offset.to_decimal().value();
+            value + offset
+        };
+
+        self.real = translate(&self.real, real);
+        self.imaginary = translate(&self.imaginary, imaginary);
+    }
+}
+
+/// The number of significant decimal digits needed to locate pixels at `zoom`.
+pub fn decimal_precision(zoom: f64) -> usize {
+    (perturbation::precision(zoom) as f64 * std::f64::consts::LOG10_2).ceil() as usize
+}
+
+/// Formats as `<real> <imaginary> <zoom>`, which [`View::from_str`] reads back exactly.
+impl Display for View {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.real, self.imaginary, self.zoom)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseViewError(String);
+
+impl Display for ParseViewError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid view: {:?} (expected `<real> <imaginary> <zoom>`)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseViewError {}
+
+/// Parses `<real> <imaginary> <zoom>`, where `real` and `imaginary` are decimals of any length.
+impl FromStr for View {
+    type Err = ParseViewError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let error = || ParseViewError(input.to_string());
+
+        let parts = input.split_whitespace().collect::<Vec<_>>();
+        let [real, imaginary, zoom] = parts[..] else {
+            return Err(error());
+        };
+
+        let zoom = zoom.parse::<f64>().map_err(|_| error())?;
+        if !(zoom.is_finite() && zoom > 0.0) {
+            return Err(error());
+        }
+
+        Ok(View {
+            real: DBig::from_str(real).map_err(|_| error())?,
+            imaginary: DBig::from_str(imaginary).map_err(|_| error())?,
+            zoom,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn views_are_formatted_and_parsed_back() {
+        for input in [
+            "-0.74529 0.113075 1",
+            "-0.74364388703715870475219150611477 0.13182590420531197049313205638514 1e30",
+            "0 -2 0.5",
+        ] {
+            let view: View = input.parse().unwrap();
+            assert_eq!(
+                view.to_string().parse::<View>().unwrap(),
+                view,
+                "{:?}",
+                input
+            );
+        }
+
+        let mut view = View::default();
+        view.set_zoom(3e12);
+        view.pan(
+            12.5,
+            -7.25,
+            screen::Size {
+                width: 1920,
+                height: 1080,
+            },
+        );
+        assert_eq!(view.to_string().parse::<View>().unwrap(), view);
+    }
+
+    #[test]
+    fn invalid_views_are_rejected() {
+        for input in [
+            "", "0 0", "0 0 1 1", "zero 0 1", "0 0 zoom", "0 0 0", "0 0 -1", "0 0 inf", "0 0 NaN",
+        ] {
+            assert!(input.parse::<View>().is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn panning_on_an_empty_screen_does_nothing() {
+        let mut view = View::default();
+        for size in [
+            screen::Size {
+                width: 0,
+                height: 0,
+            },
+            screen::Size {
+                width: 0,
+                height: 1080,
+            },
+        ] {
+            view.pan(10.0, 10.0, size);
+            view.pan(0.0, 0.0, size);
+            view.zoom_at(2.0, 10.0, 10.0, size);
+        }
+        assert_eq!(view.real(), View::default().real());
+        assert_eq!(view.imaginary(), View::default().imaginary());
+
+        view.translate(f64::NAN, 0.0);
+        view.translate(0.0, f64::INFINITY);
+        assert_eq!(view.real(), View::default().real());
+        assert_eq!(view.imaginary(), View::default().imaginary());
+    }
+}